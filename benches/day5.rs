@@ -0,0 +1,41 @@
+use aoc2022::day5;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use regex::Regex;
+
+const TEST: &str = include_str!("../input/day5/test.txt");
+const SCALE: usize = 10;
+
+/// Move parsing as it was done before the pattern was cached: one regex
+/// compilation per line.
+fn parse_moves_uncached(s: &str) -> Option<Vec<(usize, usize, usize)>> {
+    s.lines()
+        .map(|l| {
+            let re = Regex::new(r"^move (?P<nr>\d+) from (?P<from>\d+) to (?P<to>\d+)$").ok()?;
+            let caps = re.captures(l)?;
+
+            Some((
+                caps["nr"].parse().ok()?,
+                caps["from"].parse().ok()?,
+                caps["to"].parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+fn parse_moves(c: &mut Criterion) {
+    let (_, moves) = TEST.split_once("\n\n").unwrap();
+    let moves = moves.repeat(SCALE);
+    let mut group = c.benchmark_group("day5_parse_moves");
+
+    group.sample_size(10);
+    group.bench_function("cached", |b| {
+        b.iter(|| day5::parse_moves(black_box(&moves)).unwrap())
+    });
+    group.bench_function("uncached", |b| {
+        b.iter(|| parse_moves_uncached(black_box(&moves)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_moves);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+use aoc2022::day8;
+use aoc2022::grid::Grid;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const SIZE: usize = 2000;
+
+/// Random heights from a fixed-seed LCG.
+fn random_forest() -> Grid<u32> {
+    let mut state = 0x2545f4914f6cdd1du64;
+    let cells = (0..SIZE * SIZE)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % 10) as u32
+        })
+        .collect();
+
+    Grid::new(SIZE, SIZE, cells).unwrap()
+}
+
+/// Heights rising towards the bottom right, so every tree sees all the way
+/// back to the top and left edges.
+fn staircase_forest() -> Grid<u32> {
+    let cells = (0..SIZE * SIZE)
+        .map(|i| (i / SIZE + i % SIZE) as u32)
+        .collect();
+
+    Grid::new(SIZE, SIZE, cells).unwrap()
+}
+
+fn scenic_scores(c: &mut Criterion) {
+    let random = random_forest();
+    let staircase = staircase_forest();
+    let mut group = c.benchmark_group("day8_scenic_scores_2000x2000");
+
+    group.sample_size(10);
+    group.bench_function("monotonic_random", |b| {
+        b.iter(|| day8::scenic_scores(black_box(&random)))
+    });
+    group.bench_function("monotonic_staircase", |b| {
+        b.iter(|| day8::scenic_scores(black_box(&staircase)))
+    });
+    group.bench_function("per_tree_random", |b| {
+        b.iter(|| {
+            random
+                .iter()
+                .map(|((r, c), _)| day8::scenic_score(r, c, black_box(&random)).unwrap())
+                .max()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, scenic_scores);
+criterion_main!(benches);
@@ -0,0 +1,41 @@
+use aoc2022::day9::{self, Motion, Rope};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const KNOTS: usize = 1000;
+
+/// A square spiral with arms `arm` steps long, plus a few diagonals.
+fn motions(arm: usize) -> Vec<Motion> {
+    let input = format!(
+        "R {0}\nU {0}\nL {1}\nD {1}\nR {1}\nUL {0}\nDR {1}\n",
+        arm,
+        2 * arm
+    );
+
+    day9::parse_motions(&input).unwrap()
+}
+
+fn stepped(motions: &[Motion]) -> usize {
+    let mut states = Rope::new(KNOTS).simulate(motions.to_vec());
+
+    states.advance_by(usize::MAX);
+    states.rope().visited(KNOTS - 1).unwrap().len()
+}
+
+fn tail_visits(c: &mut Criterion) {
+    let short = motions(2000);
+    let long = motions(1_000_000);
+    let mut group = c.benchmark_group("day9_tail_visits_1000_knots");
+
+    group.sample_size(10);
+    group.bench_function("stepped_2000", |b| b.iter(|| stepped(black_box(&short))));
+    group.bench_function("bulk_2000", |b| {
+        b.iter(|| day9::tail_visits(KNOTS, black_box(&short)).unwrap().len())
+    });
+    group.bench_function("bulk_1000000", |b| {
+        b.iter(|| day9::tail_visits(KNOTS, black_box(&long)).unwrap().len())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, tail_visits);
+criterion_main!(benches);
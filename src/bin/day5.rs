@@ -0,0 +1,36 @@
+use aoc2022::day5;
+use std::{env, fs, process};
+
+const USAGE: &str = "usage: day5 <input> [--crane 9000|9001|bottom|capacity=N]";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let (path, model) = match &args[..] {
+        [path] => (path, "9000"),
+        [path, flag, model] if flag == "--crane" => (path, model.as_str()),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let crane = day5::crane_by_name(model).unwrap_or_else(|e| {
+        eprintln!("{}: {}", model, e);
+        process::exit(2);
+    });
+    let input = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    match day5::rearrange(&input, crane.as_ref()) {
+        Some(tops) => println!("{}", tops),
+        None => {
+            match day5::dry_run(&input, crane.as_ref()) {
+                Err(e) => eprintln!("{}: {}", path, e),
+                Ok(()) => eprintln!("{}: crane {} couldn't rearrange crates", path, model),
+            }
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use aoc2022::day8;
+use std::{env, fs, process};
+
+const USAGE: &str = "usage: day8 <input> [--separated] [--ragged] [--ppm <prefix>]";
+
+fn write(path: &str, data: &[u8]) {
+    fs::write(path, data).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| usage());
+    let mut format = day8::HeightFormat::Digits;
+    let mut ragged = false;
+    let mut prefix = None;
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--separated" => format = day8::HeightFormat::Separated,
+            "--ragged" => ragged = true,
+            "--ppm" => prefix = Some(args.next().unwrap_or_else(|| usage())),
+            _ => usage(),
+        }
+    }
+
+    let input = fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+    let heights = day8::parse_heights(&input, format, ragged).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+    let visibility = day8::sparse_visibility_map(&heights);
+    let scores = day8::sparse_viewing_distances(&heights).map(day8::Distances::scenic_score);
+
+    println!("{}", day8::render_visibility(&visibility));
+    print!("{}", day8::render_heatmap(&scores));
+
+    if let Some(prefix) = prefix {
+        write(
+            &format!("{}-visibility.ppm", prefix),
+            &day8::visibility_image(&visibility).to_ppm(),
+        );
+        write(
+            &format!("{}-scenic.ppm", prefix),
+            &day8::heatmap_image(&scores).to_ppm(),
+        );
+    }
+}
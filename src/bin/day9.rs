@@ -0,0 +1,55 @@
+use aoc2022::day9;
+use std::{env, fs, process, thread, time::Duration};
+
+const USAGE: &str = "usage: day9 <input> [--knots N] [--out <dir>]";
+const FRAME_DELAY: Duration = Duration::from_millis(50);
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| usage());
+    let mut knots = 2;
+    let mut out = None;
+
+    while let Some(flag) = args.next() {
+        match (flag.as_str(), args.next()) {
+            ("--knots", Some(n)) => {
+                knots = n.parse().ok().filter(|&n| n > 0).unwrap_or_else(|| usage())
+            }
+            ("--out", Some(dir)) => out = Some(dir),
+            _ => usage(),
+        }
+    }
+
+    let input = fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+    let motions = day9::parse_motions(&input).unwrap_or_else(|| {
+        eprintln!("{}: malformed motion list", path);
+        process::exit(1);
+    });
+    let frames = day9::animate(knots, motions);
+
+    match out {
+        Some(dir) => {
+            for (i, frame) in frames.enumerate() {
+                let file = format!("{}/frame-{:05}.txt", dir, i);
+                fs::write(&file, frame).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", file, e);
+                    process::exit(1);
+                });
+            }
+        }
+        None => {
+            for frame in frames {
+                print!("\x1b[2J\x1b[H{}", frame);
+                thread::sleep(FRAME_DELAY);
+            }
+        }
+    }
+}
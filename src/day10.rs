@@ -1,73 +1,222 @@
-use std::collections::{HashMap, HashSet};
-
-fn execute(input: &str, nr_cycles: isize) -> Option<HashMap<isize, isize>> {
-    let mut prog = input.lines();
-    let mut res = HashMap::new();
-    let mut cycle = 1;
-    let mut x = 1;
-
-    while cycle <= nr_cycles {
-        let insn = prog.next()?;
-        let parts = insn.split_whitespace().collect::<Vec<_>>();
-        let (mut cpi, dx) = match parts[..] {
-            ["addx", imm] => (2, imm.parse::<isize>().ok()?),
-            ["noop"] => (1, 0),
-            _ => return None,
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::ocr::{self, OcrError};
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    Noop,
+    Addx(isize),
+}
+
+impl Instruction {
+    /// How many cycles the instruction takes to complete.
+    pub fn cycles(self) -> usize {
+        match self {
+            Self::Noop => 1,
+            Self::Addx(_) => 2,
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["noop"] => Ok(Self::Noop),
+            ["addx", imm] => Ok(Self::Addx(imm.parse().or(Err("couldn't parse immediate"))?)),
+            _ => Err("unknown instruction"),
+        }
+    }
+}
+
+pub fn parse_program(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, l)| {
+            l.parse().map_err(|reason| ParseError {
+                line: i + 1,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// A cycle as seen from the inside: its number, counting from 1, and the
+/// value of `X` during it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    pub number: usize,
+    pub x: isize,
+}
+
+/// The handheld's CPU. It halts once it runs past the end of its program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cpu {
+    program: Vec<Instruction>,
+    pc: usize,
+    x: isize,
+    cycle: usize,
+    busy: usize,
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self {
+            program,
+            pc: 0,
+            x: 1,
+            cycle: 0,
+            busy: 0,
+        }
+    }
+
+    pub fn x(&self) -> isize {
+        self.x
+    }
+
+    /// The index of the instruction being executed.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The number of cycles completed so far.
+    pub fn cycle(&self) -> usize {
+        self.cycle
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    /// Runs a single cycle and returns what it looked like, or `None` if the
+    /// CPU has halted. Instructions take effect at the end of their last
+    /// cycle.
+    pub fn step(&mut self) -> Option<Cycle> {
+        let insn = *self.program.get(self.pc)?;
+
+        self.cycle += 1;
+        self.busy += 1;
+
+        let during = Cycle {
+            number: self.cycle,
+            x: self.x,
         };
 
-        loop {
-            res.insert(cycle, x);
+        if self.busy == insn.cycles() {
+            if let Instruction::Addx(v) = insn {
+                self.x += v;
+            }
+            self.pc += 1;
+            self.busy = 0;
+        }
 
-            cycle += 1;
-            cpi -= 1;
+        Some(during)
+    }
 
-            if cpi == 0 {
-                x += dx;
+    /// Runs at most `limit` cycles, showing each of them to `observer`, and
+    /// returns how many were run.
+    pub fn run(&mut self, limit: usize, observer: &mut dyn Observer) -> usize {
+        let mut n = 0;
+
+        while n < limit {
+            let Some(cycle) = self.step() else {
                 break;
-            }
+            };
+
+            observer.observe(cycle);
+            n += 1;
+        }
+
+        n
+    }
+}
+
+pub trait Observer {
+    fn observe(&mut self, cycle: Cycle);
+}
+
+/// Sums `number * x` over the cycles numbered 20, 60, 100, ...
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SignalStrength {
+    pub total: isize,
+}
+
+impl Observer for SignalStrength {
+    fn observe(&mut self, cycle: Cycle) {
+        if cycle.number % 40 == 20 {
+            self.total += cycle.number as isize * cycle.x;
         }
     }
+}
+
+pub const CRT_WIDTH: usize = 40;
+pub const CRT_HEIGHT: usize = 6;
+
+/// The screen, drawing one pixel per cycle. A pixel is lit when the
+/// three-pixel-wide sprite centred on `X` covers it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crt {
+    pixels: Grid<bool>,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self {
+            pixels: Grid::new(CRT_HEIGHT, CRT_WIDTH, vec![false; CRT_WIDTH * CRT_HEIGHT]).unwrap(),
+        }
+    }
+}
+
+impl Crt {
+    pub fn pixels(&self) -> &Grid<bool> {
+        &self.pixels
+    }
+
+    /// The screen as rows of `#` (lit) and `.` (dark), each ending in a
+    /// newline.
+    pub fn render(&self) -> String {
+        (0..CRT_HEIGHT)
+            .flat_map(|r| {
+                let row = self.pixels.row(r).unwrap();
+                row.iter()
+                    .map(|&lit| if lit { '#' } else { '.' })
+                    .chain(['\n'])
+            })
+            .collect()
+    }
+
+    /// The letters on the screen.
+    pub fn text(&self) -> Result<String, OcrError> {
+        ocr::read(&self.pixels)
+    }
+}
+
+impl Observer for Crt {
+    fn observe(&mut self, cycle: Cycle) {
+        let px = (cycle.number - 1) % (CRT_WIDTH * CRT_HEIGHT);
+        let (r, c) = (px / CRT_WIDTH, px % CRT_WIDTH);
 
-    Some(res)
+        self.pixels[(r, c)] = (cycle.x - c as isize).abs() <= 1;
+    }
 }
 
 pub fn part1(input: &str) -> Option<isize> {
-    let tracepoints = HashSet::from([20, 60, 100, 140, 180, 220]);
-    let trace = execute(input, 220)?;
+    let mut cpu = Cpu::new(parse_program(input).ok()?);
+    let mut signal = SignalStrength::default();
 
-    Some(
-        trace
-            .iter()
-            .filter(|(cycles, _)| tracepoints.contains(cycles))
-            .map(|(&cycles, &x)| cycles * x)
-            .sum(),
-    )
+    cpu.run(220, &mut signal);
+    Some(signal.total)
 }
 
 pub fn part2(input: &str) -> Option<String> {
-    let trace = execute(input, 240)?;
-    let pixels = (0..240)
-        .map(|px| {
-            let d = trace[&(px + 1)] - (px % 40);
-
-            if d.abs() <= 1 {
-                "#"
-            } else {
-                "."
-            }
-        })
-        .collect::<String>();
-    let mut display = pixels
-        .chars()
-        .collect::<Vec<char>>()
-        .chunks(40)
-        .map(|c| c.iter().collect::<String>())
-        .collect::<Vec<String>>()
-        .join("\n");
+    let mut cpu = Cpu::new(parse_program(input).ok()?);
+    let mut crt = Crt::default();
 
-    // for a strict match against a file w/ trailing newline
-    display.push('\n');
-    Some(display)
+    cpu.run(CRT_WIDTH * CRT_HEIGHT, &mut crt);
+    Some(crt.render())
 }
 
 #[cfg(test)]
@@ -98,4 +247,67 @@ mod tests {
     fn part2_test() {
         assert_eq!(part2(TEST), Some(PART2_TEST_EXP.to_string()));
     }
+
+    #[test]
+    fn cpu_steps() {
+        let program = parse_program("noop\naddx 3\naddx -5\n").unwrap();
+        let mut cpu = Cpu::new(program);
+        let mut cycles = Vec::new();
+
+        while let Some(c) = cpu.step() {
+            cycles.push((c.number, c.x));
+        }
+
+        assert_eq!(cycles, [(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(cpu.x(), -1);
+        assert_eq!(cpu.cycle(), 5);
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.step(), None);
+    }
+
+    #[test]
+    fn early_halt() {
+        let mut cpu = Cpu::new(parse_program(EXAMPLE).unwrap());
+        let mut signal = SignalStrength::default();
+
+        assert_eq!(cpu.run(100, &mut signal), 100);
+        assert_eq!(signal.total, 420 + 1140 + 1800);
+        assert!(!cpu.is_halted());
+
+        // a program that halts early leaves the remaining pixels dark
+        let dark = ".".repeat(CRT_WIDTH) + "\n";
+        assert_eq!(part1("noop\naddx 1\n"), Some(0));
+        assert_eq!(part1(&"noop\n".repeat(20)), Some(20));
+        assert_eq!(
+            part2("noop\n"),
+            Some(format!("#{}", &dark[1..]) + &dark.repeat(CRT_HEIGHT - 1))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse_program("noop\naddx x\n"),
+            Err(ParseError {
+                line: 2,
+                reason: "couldn't parse immediate"
+            })
+        );
+        assert_eq!(
+            parse_program("mul 2\n"),
+            Err(ParseError {
+                line: 1,
+                reason: "unknown instruction"
+            })
+        );
+    }
+
+    #[test]
+    fn crt_text() {
+        let mut cpu = Cpu::new(parse_program(TEST).unwrap());
+        let mut crt = Crt::default();
+
+        cpu.run(CRT_WIDTH * CRT_HEIGHT, &mut crt);
+        assert_eq!(crt.text(), Ok("PLULKBZH".to_string()));
+    }
 }
@@ -1,6 +1,7 @@
 use regex::Regex;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::LazyLock;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 enum Operand {
@@ -90,14 +91,14 @@ impl Monkey {
     }
 }
 
-const MONKEY_RE: &str = r"^Monkey (?P<id>\d+):\n\s+Starting items:(?P<items>[\d, ]+)\n\s+Operation: new = (?P<op0>\S+) (?P<operator>[+\*]) (?P<op1>\S+)\n\s+Test: divisible by (?P<divisor>\d+)\n\s+If true: throw to monkey (?P<target_true>\d+)\n\s+If false: throw to monkey (?P<target_false>\d+)$";
+const MONKEY_PATTERN: &str = r"^Monkey (?P<id>\d+):\n\s+Starting items:(?P<items>[\d, ]+)\n\s+Operation: new = (?P<op0>\S+) (?P<operator>[+\*]) (?P<op1>\S+)\n\s+Test: divisible by (?P<divisor>\d+)\n\s+If true: throw to monkey (?P<target_true>\d+)\n\s+If false: throw to monkey (?P<target_false>\d+)$";
+static MONKEY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(MONKEY_PATTERN).unwrap());
 
 impl FromStr for Monkey {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(MONKEY_RE).or(Err("regex compilation"))?;
-        let caps = re.captures(s).ok_or("regex match")?;
+        let caps = MONKEY_RE.captures(s).ok_or("regex match")?;
 
         let id = caps
             .name("id")
@@ -175,7 +176,7 @@ fn run_iterations(
     reduce_worry: bool,
 ) -> Option<usize> {
     let mut queues = HashMap::new();
-    let mut common_multiplier: isize = monkeys.iter().map(|(_, m)| m.divisor).product();
+    let mut common_multiplier: isize = monkeys.values().map(|m| m.divisor).product();
 
     if reduce_worry {
         common_multiplier *= 3;
@@ -197,10 +198,7 @@ fn run_iterations(
         }
     }
 
-    let mut inspected = monkeys
-        .iter()
-        .map(|(_, m)| m.nr_inspected)
-        .collect::<Vec<_>>();
+    let mut inspected = monkeys.values().map(|m| m.nr_inspected).collect::<Vec<_>>();
     inspected.sort();
 
     Some(inspected.iter().rev().take(2).product())
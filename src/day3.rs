@@ -3,17 +3,15 @@ use std::collections::HashSet;
 fn get_duplicate(inventory: &str) -> Option<u8> {
     let mid = inventory.len() / 2;
 
-    if inventory.len() % 2 != 0 {
+    if !inventory.len().is_multiple_of(2) {
         return None;
     }
 
-    let left = inventory[..mid]
-        .as_bytes()
+    let left = inventory.as_bytes()[..mid]
         .iter()
         .copied()
         .collect::<HashSet<_>>();
-    let right = inventory[mid..]
-        .as_bytes()
+    let right = inventory.as_bytes()[mid..]
         .iter()
         .copied()
         .collect::<HashSet<_>>();
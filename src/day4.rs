@@ -1,8 +1,48 @@
+use crate::error::ParseError;
 use std::str::FromStr;
 
-struct Range(isize, isize);
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Range(isize, isize);
+
+/// How reversed bounds (e.g. `7-3`) are treated when parsing a range.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RangeMode {
+    /// Reject ranges whose start is greater than their end.
+    #[default]
+    Strict,
+    /// Swap reversed bounds so that `7-3` is read as `3-7`.
+    Normalize,
+}
 
 impl Range {
+    pub fn new(start: isize, end: isize) -> Result<Self, &'static str> {
+        if start < 0 || end < 0 {
+            Err("negative bound")
+        } else if start > end {
+            Err("start greater than end")
+        } else {
+            Ok(Self(start, end))
+        }
+    }
+
+    pub fn parse(s: &str, mode: RangeMode) -> Result<Self, &'static str> {
+        // Look for the separator past the first character, so that a sign on
+        // the start isn't mistaken for it.
+        let sep = s
+            .get(1..)
+            .and_then(|t| t.find('-'))
+            .ok_or("expected 2 integers")?
+            + 1;
+        let (s1, s2) = (&s[..sep], &s[sep + 1..]);
+        let start = s1.parse::<isize>().or(Err("couldn't parse integer"))?;
+        let end = s2.parse::<isize>().or(Err("couldn't parse integer"))?;
+
+        match mode {
+            RangeMode::Normalize if start > end => Self::new(end, start),
+            _ => Self::new(start, end),
+        }
+    }
+
     pub fn contains_num(&self, i: isize) -> bool {
         self.0 <= i && i <= self.1
     }
@@ -23,49 +63,50 @@ impl FromStr for Range {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let bounds = s
-            .split('-')
-            .map(|x| x.parse::<isize>().or(Err("couldn't parse integer")))
-            .collect::<Result<Vec<_>, Self::Err>>()?;
-
-        if bounds.len() == 2 {
-            Ok(Self(bounds[0], bounds[1]))
-        } else {
-            Err("expected 2 integers")
-        }
+        Self::parse(s, RangeMode::Strict)
     }
 }
 
-pub fn part1(input: &str) -> Option<isize> {
-    let mut sum = 0;
-
-    for line in input.lines() {
-        let (s1, s2) = line.split_once(',')?;
-        let first = s1.parse::<Range>().ok()?;
-        let second = s2.parse::<Range>().ok()?;
-
-        if first.contains(&second) || second.contains(&first) {
-            sum += 1;
-        }
-    }
+pub fn parse_pairs(input: &str, mode: RangeMode) -> Result<Vec<(Range, Range)>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let err = |reason| ParseError {
+                line: i + 1,
+                reason,
+            };
+            let (s1, s2) = line
+                .split_once(',')
+                .ok_or_else(|| err("expected 2 ranges"))?;
+            let first = Range::parse(s1, mode).map_err(err)?;
+            let second = Range::parse(s2, mode).map_err(err)?;
+
+            Ok((first, second))
+        })
+        .collect()
+}
 
-    Some(sum)
+pub fn part1(input: &str) -> Option<isize> {
+    let pairs = parse_pairs(input, RangeMode::Strict).ok()?;
+
+    Some(
+        pairs
+            .iter()
+            .filter(|(first, second)| first.contains(second) || second.contains(first))
+            .count() as isize,
+    )
 }
 
 pub fn part2(input: &str) -> Option<isize> {
-    let mut sum = 0;
-
-    for line in input.lines() {
-        let (s1, s2) = line.split_once(',')?;
-        let first = s1.parse::<Range>().ok()?;
-        let second = s2.parse::<Range>().ok()?;
-
-        if first.overlaps(&second) {
-            sum += 1;
-        }
-    }
-
-    Some(sum)
+    let pairs = parse_pairs(input, RangeMode::Strict).ok()?;
+
+    Some(
+        pairs
+            .iter()
+            .filter(|(first, second)| first.overlaps(second))
+            .count() as isize,
+    )
 }
 
 #[cfg(test)]
@@ -94,4 +135,43 @@ mod tests {
     fn part2_test() {
         assert_eq!(part2(TEST), Some(798))
     }
+
+    #[test]
+    fn reversed_range() {
+        assert_eq!("7-3".parse::<Range>(), Err("start greater than end"));
+        assert_eq!(Range::parse("7-3", RangeMode::Normalize), Ok(Range(3, 7)));
+    }
+
+    #[test]
+    fn negative_range() {
+        assert_eq!("3--5".parse::<Range>(), Err("negative bound"));
+        assert_eq!("-3-5".parse::<Range>(), Err("negative bound"));
+        assert_eq!("-3--5".parse::<Range>(), Err("negative bound"));
+        assert_eq!("-3".parse::<Range>(), Err("expected 2 integers"));
+        assert_eq!(
+            Range::parse("3--5", RangeMode::Normalize),
+            Err("negative bound")
+        );
+    }
+
+    #[test]
+    fn malformed_pair_line() {
+        let input = "2-4,6-8\n2-3\n5-7,9-7\n";
+
+        assert_eq!(
+            parse_pairs(input, RangeMode::Normalize),
+            Err(ParseError {
+                line: 2,
+                reason: "expected 2 ranges"
+            })
+        );
+        assert_eq!(
+            parse_pairs("2-4,6-8\n5-7,9-7\n", RangeMode::Strict),
+            Err(ParseError {
+                line: 2,
+                reason: "start greater than end"
+            })
+        );
+        assert_eq!(part1("2-4,6-8\n5-7,9-7\n"), None);
+    }
 }
@@ -1,40 +1,102 @@
+use crate::error::ParseError;
 use regex::Regex;
+use std::fmt;
 use std::str::FromStr;
+use std::sync::LazyLock;
 
-fn parse_stacks(s: &str) -> Option<Vec<String>> {
-    let mut iter = s.lines().rev();
-    let mut stacks = Vec::new();
+/// A stack of crate labels, bottom first.
+pub type Stack = Vec<String>;
 
-    // allocate stacks
-    for _ in iter.next()?.split_whitespace() {
-        stacks.push(String::new());
+/// Splits a line into whitespace-delimited tokens along with their byte span.
+fn tokens(line: &str) -> Vec<(usize, usize, &str)> {
+    let mut res = Vec::new();
+    let mut start = None;
+
+    for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                res.push((s, i, &line[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    res
+}
+
+/// Parses the stack drawing, using the label row to locate each stack.
+///
+/// A crate belongs to the stack whose label lies underneath it, so labels
+/// may be several characters wide and lines needn't carry trailing spaces.
+pub fn parse_stacks(s: &str) -> Result<Vec<Stack>, ParseError> {
+    let lines = s.lines().collect::<Vec<_>>();
+    let err = |line, reason| ParseError { line, reason };
+    let (labels, rows) = lines.split_last().ok_or_else(|| err(1, "empty drawing"))?;
+    let columns = tokens(labels);
+
+    if columns.is_empty() {
+        return Err(err(lines.len(), "missing stack labels"));
+    }
+
+    for (i, (_, _, label)) in columns.iter().enumerate() {
+        if label.parse::<usize>() != Ok(i + 1) {
+            return Err(err(lines.len(), "stack labels must count up from 1"));
+        }
     }
 
-    for line in iter {
-        for (i, element) in line.chars().skip(1).step_by(4).enumerate() {
-            if !element.is_whitespace() {
-                stacks[i].push(element);
+    let mut stacks = vec![Stack::new(); columns.len()];
+
+    for (level, (row_idx, line)) in rows.iter().enumerate().rev().enumerate() {
+        let line_nr = row_idx + 1;
+
+        for (start, end, token) in tokens(line) {
+            let label = token
+                .strip_prefix('[')
+                .and_then(|t| t.strip_suffix(']'))
+                .filter(|l| !l.is_empty() && !l.contains(['[', ']']))
+                .ok_or_else(|| err(line_nr, "malformed crate"))?;
+            let below = columns
+                .iter()
+                .enumerate()
+                .filter(|(_, &(s, e, _))| start < e && s < end)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            let stack = match below[..] {
+                [i] => &mut stacks[i],
+                [] => return Err(err(line_nr, "crate isn't above any stack label")),
+                _ => return Err(err(line_nr, "crate spans several stack labels")),
+            };
+
+            if stack.len() > level {
+                return Err(err(line_nr, "crates overlap in the same stack"));
+            } else if stack.len() < level {
+                return Err(err(line_nr, "crate floats above an empty slot"));
             }
+
+            stack.push(label.to_string());
         }
     }
 
-    Some(stacks)
+    Ok(stacks)
 }
 
 #[derive(Copy, Clone, Debug, Default)]
-struct Move {
+pub struct Move {
     pub to: usize,
     pub from: usize,
     pub nr: usize,
 }
 
+static MOVE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^move (?P<nr>\d+) from (?P<from>\d+) to (?P<to>\d+)$").unwrap());
+
 impl FromStr for Move {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"^move (?P<nr>\d+) from (?P<from>\d+) to (?P<to>\d+)$")
-            .or(Err("couldn't compile regex"))?;
-        let caps = re.captures(s).ok_or("regex match failed")?;
+        let caps = MOVE_RE.captures(s).ok_or("regex match failed")?;
 
         let to = caps["to"].parse().or(Err("couldn't parse destination"))?;
         let from = caps["from"].parse().or(Err("couldn't parse source"))?;
@@ -44,52 +106,326 @@ impl FromStr for Move {
     }
 }
 
-fn parse_moves(s: &str) -> Option<Vec<Move>> {
-    s.lines().map(|l| l.parse::<Move>().ok()).collect()
+pub fn parse_moves(s: &str) -> Result<Vec<Move>, ParseError> {
+    s.lines()
+        .enumerate()
+        .map(|(i, l)| {
+            l.parse::<Move>().map_err(|reason| ParseError {
+                line: i + 1,
+                reason,
+            })
+        })
+        .collect()
 }
 
-pub fn part1(input: &str) -> Option<String> {
-    let (s1, s2) = input.split_once("\n\n")?;
-    let mut stacks = parse_stacks(s1)?;
-    let moves = parse_moves(s2)?;
-    let mut res = String::new();
+/// A move that can't be carried out, along with the stacks just before it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoveError {
+    pub line: usize,
+    pub reason: &'static str,
+    pub stacks: Vec<Stack>,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} (stacks:", self.line, self.reason)?;
+
+        for (i, stack) in self.stacks.iter().enumerate() {
+            write!(f, " {}:", i + 1)?;
+            for label in stack {
+                write!(f, "[{}]", label)?;
+            }
+        }
+
+        write!(f, ")")
+    }
+}
 
-    for m in moves {
-        for _ in 0..m.nr {
-            let c = stacks[m.from - 1].pop()?;
+impl std::error::Error for MoveError {}
 
-            stacks[m.to - 1].push(c);
+/// Why an input can't be rearranged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputError {
+    Parse(ParseError),
+    Move(MoveError),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => e.fmt(f),
+            Self::Move(e) => e.fmt(f),
         }
     }
+}
+
+impl std::error::Error for InputError {}
+
+/// A crane model, deciding how crates get from one stack to another.
+pub trait Crane {
+    /// Removes `nr` crates from `src`, returning them in the order they land
+    /// on the destination stack (bottom first). Returns `None`, leaving `src`
+    /// untouched, if the crates can't be lifted.
+    fn lift(&self, src: &mut Stack, nr: usize) -> Option<Stack>;
+}
 
-    for mut s in stacks {
-        res.push(s.pop()?);
+/// Moves crates one at a time, reversing their order.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, src: &mut Stack, nr: usize) -> Option<Stack> {
+        let lifted = src.split_off(src.len().checked_sub(nr)?);
+
+        Some(lifted.into_iter().rev().collect())
     }
+}
+
+/// Moves all the crates at once, keeping their order.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CrateMover9001;
 
-    Some(res)
+impl Crane for CrateMover9001 {
+    fn lift(&self, src: &mut Stack, nr: usize) -> Option<Stack> {
+        Some(src.split_off(src.len().checked_sub(nr)?))
+    }
 }
 
-pub fn part2(input: &str) -> Option<String> {
-    let (s1, s2) = input.split_once("\n\n")?;
-    let mut stacks = parse_stacks(s1)?;
-    let moves = parse_moves(s2)?;
-    let mut res = String::new();
+/// Moves at most `.0` crates per lift, keeping the order within a lift.
+#[derive(Copy, Clone, Debug)]
+pub struct LimitedCapacity(pub usize);
+
+impl Crane for LimitedCapacity {
+    fn lift(&self, src: &mut Stack, nr: usize) -> Option<Stack> {
+        if self.0 == 0 || nr > src.len() {
+            return None;
+        }
+
+        let mut lifted = Stack::new();
+        let mut remaining = nr;
 
-    for m in moves {
-        let mut tmp = String::new();
+        while remaining > 0 {
+            let chunk = remaining.min(self.0);
 
-        for _ in 0..m.nr {
-            tmp.push(stacks[m.from - 1].pop()?);
+            lifted.append(&mut src.split_off(src.len() - chunk));
+            remaining -= chunk;
         }
 
-        tmp.chars().rev().for_each(|c| stacks[m.to - 1].push(c));
+        Some(lifted)
     }
+}
 
-    for mut s in stacks {
-        res.push(s.pop()?);
+/// Pulls crates out from the bottom of the source stack, keeping their order.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BottomLift;
+
+impl Crane for BottomLift {
+    fn lift(&self, src: &mut Stack, nr: usize) -> Option<Stack> {
+        if nr > src.len() {
+            return None;
+        }
+
+        Some(src.drain(..nr).collect())
     }
+}
+
+/// Selects a crane model by name: `9000`, `9001`, `bottom` or `capacity=N`.
+pub fn crane_by_name(name: &str) -> Result<Box<dyn Crane>, &'static str> {
+    match name.split_once('=') {
+        Some(("capacity", n)) => {
+            let capacity = n.parse().or(Err("couldn't parse capacity"))?;
 
-    Some(res)
+            Ok(Box::new(LimitedCapacity(capacity)))
+        }
+        Some(_) => Err("unknown crane model"),
+        None => match name {
+            "9000" => Ok(Box::new(CrateMover9000)),
+            "9001" => Ok(Box::new(CrateMover9001)),
+            "bottom" => Ok(Box::new(BottomLift)),
+            _ => Err("unknown crane model"),
+        },
+    }
+}
+
+/// Carries out `m` on `stacks` with `crane`. On failure the stacks are left
+/// as they were and the reason is returned.
+pub fn apply_move(stacks: &mut [Stack], m: &Move, crane: &dyn Crane) -> Result<(), &'static str> {
+    if m.from == 0 || m.from > stacks.len() {
+        return Err("no such source stack");
+    } else if m.to == 0 || m.to > stacks.len() {
+        return Err("no such destination stack");
+    } else if stacks[m.from - 1].len() < m.nr {
+        return Err("not enough crates on source stack");
+    }
+
+    let mut lifted = crane
+        .lift(&mut stacks[m.from - 1], m.nr)
+        .ok_or("crane can't lift these crates")?;
+
+    stacks[m.to - 1].append(&mut lifted);
+    Ok(())
+}
+
+fn top_crates(stacks: &[Stack]) -> Option<String> {
+    stacks.iter().map(|s| s.last().cloned()).collect()
+}
+
+/// Crate stacks that moves are applied to one at a time.
+///
+/// Every intermediate arrangement is kept so that any step can be inspected
+/// or rendered, and moves can be undone and redone.
+#[derive(Clone, Debug)]
+pub struct CrateYard {
+    history: Vec<Vec<Stack>>,
+    step: usize,
+}
+
+impl CrateYard {
+    pub fn new(stacks: Vec<Stack>) -> Self {
+        Self {
+            history: vec![stacks],
+            step: 0,
+        }
+    }
+
+    /// Applies a move to the current arrangement using the given crane,
+    /// discarding any undone steps.
+    pub fn apply(&mut self, m: &Move, crane: &dyn Crane) -> Option<()> {
+        let mut stacks = self.stacks().to_vec();
+
+        apply_move(&mut stacks, m, crane).ok()?;
+
+        self.history.truncate(self.step + 1);
+        self.history.push(stacks);
+        self.step += 1;
+
+        Some(())
+    }
+
+    pub fn undo(&mut self) -> bool {
+        if self.step == 0 {
+            return false;
+        }
+
+        self.step -= 1;
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if self.step + 1 >= self.history.len() {
+            return false;
+        }
+
+        self.step += 1;
+        true
+    }
+
+    /// Number of moves applied to reach the current arrangement.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn stacks(&self) -> &[Stack] {
+        &self.history[self.step]
+    }
+
+    /// The arrangement after `step` moves, including undone ones.
+    pub fn stacks_at(&self, step: usize) -> Option<&[Stack]> {
+        self.history.get(step).map(|s| &s[..])
+    }
+
+    pub fn tops(&self) -> Option<String> {
+        top_crates(self.stacks())
+    }
+
+    pub fn render(&self) -> String {
+        render_stacks(self.stacks())
+    }
+
+    pub fn render_at(&self, step: usize) -> Option<String> {
+        self.stacks_at(step).map(render_stacks)
+    }
+}
+
+/// Draws stacks the way the puzzle input does, labels row included. Columns
+/// are widened to fit the longest crate label.
+fn render_stacks(stacks: &[Stack]) -> String {
+    let height = stacks.iter().map(|s| s.len()).max().unwrap_or_default();
+    let width = stacks.iter().flatten().map(|l| l.len()).max().unwrap_or(1) + 2;
+    let mut lines = Vec::new();
+
+    for level in (0..height).rev() {
+        let row = stacks
+            .iter()
+            .map(|s| match s.get(level) {
+                Some(l) => format!("{:<width$}", format!("[{}]", l)),
+                None => " ".repeat(width),
+            })
+            .collect::<Vec<_>>();
+
+        lines.push(row.join(" "));
+    }
+
+    let labels = (1..=stacks.len())
+        .map(|i| format!("{:^width$}", i))
+        .collect::<Vec<_>>();
+    lines.push(labels.join(" "));
+
+    let mut drawing = lines.join("\n");
+    drawing.push('\n');
+    drawing
+}
+
+/// Carries out the moves in `input` with `crane` one line at a time,
+/// stopping at the first line that is malformed or can't be carried out.
+/// Line numbers count from the top of the drawing.
+pub fn dry_run(input: &str, crane: &dyn Crane) -> Result<(), InputError> {
+    let (s1, s2) = input
+        .split_once("\n\n")
+        .ok_or(InputError::Parse(ParseError {
+            line: input.lines().count() + 1,
+            reason: "missing move list",
+        }))?;
+    let offset = s1.lines().count() + 1;
+    let mut stacks = parse_stacks(s1).map_err(InputError::Parse)?;
+
+    for (i, l) in s2.lines().enumerate() {
+        let line = i + 1 + offset;
+        let m = l
+            .parse::<Move>()
+            .map_err(|reason| InputError::Parse(ParseError { line, reason }))?;
+
+        if let Err(reason) = apply_move(&mut stacks, &m, crane) {
+            return Err(InputError::Move(MoveError {
+                line,
+                reason,
+                stacks,
+            }));
+        }
+    }
+
+    Ok(())
+}
+
+/// Rearranges the stacks described by `input` with the given crane and
+/// returns the crates that end up on top.
+pub fn rearrange(input: &str, crane: &dyn Crane) -> Option<String> {
+    let (s1, s2) = input.split_once("\n\n")?;
+    let mut stacks = parse_stacks(s1).ok()?;
+
+    for m in parse_moves(s2).ok()? {
+        apply_move(&mut stacks, &m, crane).ok()?;
+    }
+
+    top_crates(&stacks)
+}
+
+pub fn part1(input: &str) -> Option<String> {
+    rearrange(input, &CrateMover9000)
+}
+
+pub fn part2(input: &str) -> Option<String> {
+    rearrange(input, &CrateMover9001)
 }
 
 #[cfg(test)]
@@ -118,4 +454,226 @@ mod tests {
     fn part2_test() {
         assert_eq!(part2(TEST), Some(String::from("RNLFDJMCT")))
     }
+
+    #[test]
+    fn yard_render_round_trip() {
+        let (drawing, _) = EXAMPLE.split_once("\n\n").unwrap();
+        let yard = CrateYard::new(parse_stacks(drawing).unwrap());
+
+        assert_eq!(yard.render(), format!("{}\n", drawing));
+    }
+
+    #[test]
+    fn yard_undo_redo() {
+        let (drawing, moves) = EXAMPLE.split_once("\n\n").unwrap();
+        let mut yard = CrateYard::new(parse_stacks(drawing).unwrap());
+
+        for m in parse_moves(moves).unwrap() {
+            yard.apply(&m, &CrateMover9000).unwrap();
+        }
+
+        assert_eq!(yard.step(), 4);
+        assert_eq!(yard.tops(), Some(String::from("CMZ")));
+
+        assert!(yard.undo());
+        assert!(yard.undo());
+        assert_eq!(yard.step(), 2);
+        assert_eq!(
+            yard.render(),
+            "        [Z]\n        [N]\n    [C] [D]\n    [M] [P]\n 1   2   3 \n"
+        );
+
+        assert!(yard.redo());
+        assert_eq!(yard.stacks(), yard.stacks_at(3).unwrap());
+
+        // applying a move after undoing drops the undone steps
+        yard.apply(&"move 1 from 3 to 1".parse().unwrap(), &CrateMover9000)
+            .unwrap();
+        assert!(!yard.redo());
+        assert_eq!(yard.stacks_at(4).unwrap()[0].concat(), "CMZ");
+    }
+
+    #[test]
+    fn crane_models() {
+        let lift = |crane: &dyn Crane, nr| {
+            let mut src = "ABCDE".chars().map(String::from).collect();
+            let lifted = crane.lift(&mut src, nr)?;
+
+            Some((src.concat(), lifted.concat()))
+        };
+
+        assert_eq!(lift(&CrateMover9000, 3), Some(("AB".into(), "EDC".into())));
+        assert_eq!(lift(&CrateMover9001, 3), Some(("AB".into(), "CDE".into())));
+        assert_eq!(
+            lift(&LimitedCapacity(2), 5),
+            Some(("".into(), "DEBCA".into()))
+        );
+        assert_eq!(lift(&BottomLift, 2), Some(("CDE".into(), "AB".into())));
+        assert_eq!(lift(&CrateMover9001, 6), None);
+        assert_eq!(lift(&LimitedCapacity(0), 1), None);
+    }
+
+    #[test]
+    fn crane_selection() {
+        let crane = crane_by_name("capacity=1").unwrap();
+        assert_eq!(rearrange(EXAMPLE, crane.as_ref()), part1(EXAMPLE));
+
+        let crane = crane_by_name("9001").unwrap();
+        assert_eq!(rearrange(EXAMPLE, crane.as_ref()), part2(EXAMPLE));
+
+        assert!(crane_by_name("9002").is_err());
+        assert!(crane_by_name("capacity=x").is_err());
+    }
+
+    #[test]
+    fn drawing_wide_labels() {
+        let drawing = "[AB]\n[CD] [E]\n 1    2";
+        let stacks = parse_stacks(drawing).unwrap();
+
+        assert_eq!(stacks, vec![vec!["CD", "AB"], vec!["E"]]);
+        assert_eq!(
+            CrateYard::new(stacks).render(),
+            "[AB]     \n[CD] [E] \n 1    2  \n"
+        );
+    }
+
+    #[test]
+    fn drawing_many_stacks() {
+        let mut stacks = (0..12).map(|i| vec![i.to_string()]).collect::<Vec<_>>();
+        stacks[10].push(String::from("X"));
+
+        let drawing = render_stacks(&stacks);
+        let trimmed = drawing
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(parse_stacks(&drawing), Ok(stacks.clone()));
+        assert_eq!(parse_stacks(&trimmed), Ok(stacks));
+        assert_eq!(
+            parse_stacks("[A] [B]\n 1   2   3   4   5   6   7   8   9   10"),
+            Ok(vec![
+                vec![String::from("A")],
+                vec![String::from("B")],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+            ])
+        );
+    }
+
+    #[test]
+    fn drawing_errors() {
+        let err = |line, reason| Err(ParseError { line, reason });
+
+        assert_eq!(parse_stacks(""), err(1, "empty drawing"));
+        assert_eq!(
+            parse_stacks("[A]\n 1   3"),
+            err(2, "stack labels must count up from 1")
+        );
+        assert_eq!(parse_stacks("[A\n 1"), err(1, "malformed crate"));
+        assert_eq!(
+            parse_stacks("[A] [B]\n 1"),
+            err(1, "crate isn't above any stack label")
+        );
+        assert_eq!(
+            parse_stacks("[A]\n\n 1"),
+            err(1, "crate floats above an empty slot")
+        );
+        assert_eq!(
+            parse_stacks("[AAAAA]\n 1 2"),
+            err(1, "crate spans several stack labels")
+        );
+        assert_eq!(parse_stacks("[A][B]\n 1"), err(1, "malformed crate"));
+    }
+
+    #[test]
+    fn apply_moves_in_place() {
+        let mut stacks = parse_stacks("    [B]\n[A] [C]\n 1   2").unwrap();
+        let m = |s: &str| s.parse::<Move>().unwrap();
+
+        assert_eq!(
+            apply_move(&mut stacks, &m("move 2 from 2 to 1"), &CrateMover9001),
+            Ok(())
+        );
+        assert_eq!(top_crates(&stacks), None);
+        assert_eq!(
+            apply_move(&mut stacks, &m("move 3 from 1 to 2"), &LimitedCapacity(0)),
+            Err("crane can't lift these crates")
+        );
+        assert_eq!(
+            apply_move(&mut stacks, &m("move 1 from 3 to 2"), &CrateMover9000),
+            Err("no such source stack")
+        );
+        assert_eq!(stacks, [vec!["A", "C", "B"], vec![]]);
+    }
+
+    #[test]
+    fn dry_run_moves() {
+        assert_eq!(dry_run(EXAMPLE, &CrateMover9000), Ok(()));
+        assert_eq!(dry_run(TEST, &CrateMover9001), Ok(()));
+
+        let input = "[A]\n 1   2\n\nmove 1 from 1 to 2\nmove 2 from 2 to 1\n";
+        let err = dry_run(input, &CrateMover9000).unwrap_err();
+
+        assert_eq!(
+            err,
+            InputError::Move(MoveError {
+                line: 5,
+                reason: "not enough crates on source stack",
+                stacks: vec![vec![], vec!["A".to_string()]],
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "line 5: not enough crates on source stack (stacks: 1: 2:[A])"
+        );
+        assert_eq!(part1(input), None);
+
+        assert_eq!(
+            dry_run("[A]\n 1\n\nmove 1 from 0 to 1\n", &CrateMover9000),
+            Err(InputError::Move(MoveError {
+                line: 4,
+                reason: "no such source stack",
+                stacks: vec![vec!["A".to_string()]],
+            }))
+        );
+        assert_eq!(
+            dry_run("[A]\n[B]\n 1\n\nmove 1 from 1 to 1\n", &LimitedCapacity(0)),
+            Err(InputError::Move(MoveError {
+                line: 5,
+                reason: "crane can't lift these crates",
+                stacks: vec![vec!["B".to_string(), "A".to_string()]],
+            }))
+        );
+    }
+
+    #[test]
+    fn dry_run_stops_at_first_bad_line() {
+        assert_eq!(
+            dry_run(
+                "[A]\n 1\n\nmove 1 from 1 to 1\nmove one from 1 to 1\n",
+                &CrateMover9000
+            ),
+            Err(InputError::Parse(ParseError {
+                line: 5,
+                reason: "regex match failed",
+            }))
+        );
+
+        let err = dry_run(
+            "[A]\n 1   2\n\nmove 2 from 1 to 2\nmove one from 1 to 1\n",
+            &CrateMover9000,
+        );
+        assert!(matches!(
+            err,
+            Err(InputError::Move(MoveError { line: 4, .. }))
+        ));
+    }
 }
@@ -1,19 +1,253 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::hash::Hash;
 
-fn find_marker(input: &str, marker_len: usize) -> Option<usize> {
-    let chars = input.chars().collect::<Vec<char>>();
+/// Returns the number of items read once the last `marker_len` of them are
+/// all distinct.
+///
+/// Keeps a count per item in the window and the number of items that appear
+/// more than once in it, so each step costs O(1) regardless of `marker_len`.
+fn find_distinct<T: Copy + Eq + Hash>(items: &[T], marker_len: usize) -> Option<usize> {
+    if marker_len == 0 {
+        return Some(0);
+    }
 
-    for (i, window) in chars.windows(marker_len).enumerate() {
-        let set = window.iter().copied().collect::<HashSet<char>>();
+    let mut counts = HashMap::<T, usize>::new();
+    let mut dups = 0;
+
+    for (i, &item) in items.iter().enumerate() {
+        let count = counts.entry(item).or_default();
+        *count += 1;
+        if *count == 2 {
+            dups += 1;
+        }
 
-        if set.len() == marker_len {
-            return Some(i + marker_len);
+        if i >= marker_len {
+            let count = counts.entry(items[i - marker_len]).or_default();
+            *count -= 1;
+            if *count == 1 {
+                dups -= 1;
+            }
+        }
+
+        if i + 1 >= marker_len && dups == 0 {
+            return Some(i + 1);
         }
     }
 
     None
 }
 
+/// Byte-oriented version of [`find_marker`], using a plain frequency array
+/// rather than a map.
+pub fn find_marker_bytes(input: &[u8], marker_len: usize) -> Option<usize> {
+    if marker_len == 0 {
+        return Some(0);
+    }
+
+    let mut counts = [0usize; 256];
+    let mut dups = 0;
+
+    for (i, &b) in input.iter().enumerate() {
+        counts[b as usize] += 1;
+        if counts[b as usize] == 2 {
+            dups += 1;
+        }
+
+        if i >= marker_len {
+            let old = input[i - marker_len] as usize;
+            counts[old] -= 1;
+            if counts[old] == 1 {
+                dups -= 1;
+            }
+        }
+
+        if i + 1 >= marker_len && dups == 0 {
+            return Some(i + 1);
+        }
+    }
+
+    None
+}
+
+/// Finds the end of the first run of `marker_len` distinct characters,
+/// counted in characters.
+pub fn find_marker(input: &str, marker_len: usize) -> Option<usize> {
+    if input.is_ascii() {
+        return find_marker_bytes(input.as_bytes(), marker_len);
+    }
+
+    let chars = input.chars().collect::<Vec<char>>();
+
+    find_distinct(&chars, marker_len)
+}
+
+pub const START_OF_PACKET: usize = 4;
+pub const START_OF_MESSAGE: usize = 14;
+
+#[derive(Clone, Debug)]
+struct Watcher {
+    len: usize,
+    counts: [usize; 256],
+    dups: usize,
+    found: Option<usize>,
+}
+
+/// Incremental marker detection over a stream of bytes.
+///
+/// Several marker lengths can be watched at once. Memory use only depends on
+/// the longest of them, not on how much of the stream has been seen.
+#[derive(Clone, Debug)]
+pub struct MarkerDetector {
+    window: Vec<u8>,
+    pos: usize,
+    watchers: Vec<Watcher>,
+}
+
+impl MarkerDetector {
+    pub fn new(marker_lens: &[usize]) -> Self {
+        let watchers = marker_lens
+            .iter()
+            .map(|&len| Watcher {
+                len,
+                counts: [0; 256],
+                dups: 0,
+                found: if len == 0 { Some(0) } else { None },
+            })
+            .collect();
+
+        Self {
+            window: vec![0; marker_lens.iter().copied().max().unwrap_or_default()],
+            pos: 0,
+            watchers,
+        }
+    }
+
+    /// Watches for both start-of-packet and start-of-message markers.
+    pub fn packets_and_messages() -> Self {
+        Self::new(&[START_OF_PACKET, START_OF_MESSAGE])
+    }
+
+    /// Consumes one byte. Returns the number of bytes consumed so far if this
+    /// byte completes the first marker of at least one watched length.
+    pub fn push(&mut self, byte: u8) -> Option<usize> {
+        let pos = self.pos;
+        let mut completed = false;
+
+        for w in self.watchers.iter_mut().filter(|w| w.len > 0) {
+            w.counts[byte as usize] += 1;
+            if w.counts[byte as usize] == 2 {
+                w.dups += 1;
+            }
+
+            if pos >= w.len {
+                let old = self.window[(pos - w.len) % self.window.len()] as usize;
+                w.counts[old] -= 1;
+                if w.counts[old] == 1 {
+                    w.dups -= 1;
+                }
+            }
+
+            if w.found.is_none() && pos + 1 >= w.len && w.dups == 0 {
+                w.found = Some(pos + 1);
+                completed = true;
+            }
+        }
+
+        if !self.window.is_empty() {
+            let idx = pos % self.window.len();
+            self.window[idx] = byte;
+        }
+        self.pos += 1;
+
+        if completed {
+            Some(self.pos)
+        } else {
+            None
+        }
+    }
+
+    /// Consumes a chunk of bytes, returning the last position at which a
+    /// marker was completed.
+    pub fn feed(&mut self, bytes: &[u8]) -> Option<usize> {
+        bytes.iter().filter_map(|&b| self.push(b)).last()
+    }
+
+    /// Where the first marker of the given length ended, if seen yet.
+    pub fn marker(&self, marker_len: usize) -> Option<usize> {
+        self.watchers
+            .iter()
+            .find(|w| w.len == marker_len)
+            .and_then(|w| w.found)
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Forgets everything seen so far, keeping the watched lengths.
+    pub fn reset(&mut self) {
+        let lens = self.watchers.iter().map(|w| w.len).collect::<Vec<_>>();
+
+        *self = Self::new(&lens);
+    }
+}
+
+/// One frame of a datastream: a marker followed by its payload.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// Offset of the marker that opens the frame.
+    pub marker: usize,
+    /// Offset of the first payload byte, just past the marker.
+    pub offset: usize,
+    pub payload: &'a [u8],
+}
+
+/// Splits a datastream into frames, each opened by a marker of `marker_len`
+/// distinct bytes and running up to the next one (or the end of the stream).
+///
+/// Anything before the first marker isn't part of a frame and is skipped.
+#[derive(Clone, Debug)]
+pub struct FrameDecoder<'a> {
+    data: &'a [u8],
+    marker_len: usize,
+    pos: usize,
+}
+
+impl<'a> FrameDecoder<'a> {
+    pub fn new(data: &'a [u8], marker_len: usize) -> Self {
+        Self {
+            data,
+            marker_len,
+            pos: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for FrameDecoder<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.marker_len == 0 || self.pos >= self.data.len() {
+            return None;
+        }
+
+        let offset = self.pos + find_marker_bytes(&self.data[self.pos..], self.marker_len)?;
+        let end = match find_marker_bytes(&self.data[offset..], self.marker_len) {
+            Some(next) => offset + next - self.marker_len,
+            None => self.data.len(),
+        };
+
+        self.pos = end;
+
+        Some(Frame {
+            marker: offset - self.marker_len,
+            offset,
+            payload: &self.data[offset..end],
+        })
+    }
+}
+
 pub fn part1(input: &str) -> Option<usize> {
     find_marker(input, 4)
 }
@@ -62,4 +296,100 @@ mod tests {
     fn part2_test() {
         assert_eq!(part2(TEST), Some(2803))
     }
+
+    #[test]
+    fn marker_paths_agree() {
+        for line in PART1_EXAMPLE.lines().chain(PART2_EXAMPLE.lines()) {
+            let (input, _) = line.split_once(',').unwrap();
+            let chars = input.chars().collect::<Vec<_>>();
+
+            for marker_len in 1..=input.len() + 1 {
+                assert_eq!(
+                    find_marker_bytes(input.as_bytes(), marker_len),
+                    find_distinct(&chars, marker_len)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn marker_non_ascii() {
+        assert_eq!(find_marker("ééàéàü", 3), Some(6));
+        assert_eq!(find_marker("ééé", 2), None);
+        assert_eq!(find_marker("abc", 0), Some(0));
+    }
+
+    #[test]
+    fn detector_matches_find_marker() {
+        for line in PART1_EXAMPLE.lines().chain(PART2_EXAMPLE.lines()) {
+            let (input, _) = line.split_once(',').unwrap();
+            let mut detector = MarkerDetector::packets_and_messages();
+            let mut hits = Vec::new();
+
+            for &b in input.as_bytes() {
+                hits.extend(detector.push(b));
+            }
+
+            let packet = find_marker(input, START_OF_PACKET);
+            let message = find_marker(input, START_OF_MESSAGE);
+
+            assert_eq!(detector.marker(START_OF_PACKET), packet);
+            assert_eq!(detector.marker(START_OF_MESSAGE), message);
+            assert_eq!(hits, packet.into_iter().chain(message).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn detector_chunks() {
+        let mut detector = MarkerDetector::new(&[START_OF_MESSAGE]);
+
+        for chunk in TEST.as_bytes().chunks(7) {
+            detector.feed(chunk);
+        }
+
+        assert_eq!(detector.marker(START_OF_MESSAGE), Some(2803));
+        assert_eq!(detector.marker(START_OF_PACKET), None);
+        assert_eq!(detector.position(), TEST.len());
+
+        detector.reset();
+        assert_eq!(detector.position(), 0);
+        assert_eq!(detector.marker(START_OF_MESSAGE), None);
+    }
+
+    #[test]
+    fn frames() {
+        let frames = FrameDecoder::new(b"aabcdxxxxefghyy", 4).collect::<Vec<_>>();
+
+        assert_eq!(
+            frames,
+            vec![
+                Frame {
+                    marker: 1,
+                    offset: 5,
+                    payload: b"xxx",
+                },
+                Frame {
+                    marker: 8,
+                    offset: 12,
+                    payload: b"hyy",
+                },
+            ]
+        );
+        assert_eq!(FrameDecoder::new(b"aaaa", 4).next(), None);
+    }
+
+    #[test]
+    fn frames_cover_stream() {
+        let data = TEST.trim().as_bytes();
+        let frames = FrameDecoder::new(data, START_OF_PACKET).collect::<Vec<_>>();
+
+        assert_eq!(frames[0].offset, 1142);
+
+        for pair in frames.windows(2) {
+            assert_eq!(pair[0].offset + pair[0].payload.len(), pair[1].marker);
+        }
+
+        let last = frames.last().unwrap();
+        assert_eq!(last.offset + last.payload.len(), data.len());
+    }
 }
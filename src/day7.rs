@@ -1,62 +1,587 @@
-use std::collections::HashMap;
+use crate::error::ParseError;
+use std::str::FromStr;
 
-fn compute_dirs(input: &str) -> Option<HashMap<String, usize>> {
-    let mut dirs = HashMap::new();
-    let mut path = Vec::new();
+pub type NodeId = usize;
 
-    for line in input.lines() {
-        let parts = line.split_whitespace().collect::<Vec<_>>();
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Dir(Vec<NodeId>),
+    File(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub kind: NodeKind,
+}
+
+impl Node {
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, NodeKind::Dir(_))
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        match &self.kind {
+            NodeKind::Dir(children) => children,
+            NodeKind::File(_) => &[],
+        }
+    }
+}
+
+/// A filesystem reconstructed from a terminal transcript.
+///
+/// Nodes live in a single arena and refer to each other by index. A node is
+/// always added after its parent, so parents have lower ids than children.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FsTree {
+    nodes: Vec<Node>,
+}
+
+impl Default for FsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FsTree {
+    pub const ROOT: NodeId = 0;
+
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: String::from("/"),
+                parent: None,
+                kind: NodeKind::Dir(Vec::new()),
+            }],
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id)?.parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.node(id).map(Node::children).unwrap_or_default()
+    }
+
+    pub fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.children(id)
+            .iter()
+            .copied()
+            .find(|&c| self.nodes[c].name == name)
+    }
+
+    fn add(&mut self, parent: NodeId, name: &str, kind: NodeKind) -> Option<NodeId> {
+        let id = self.nodes.len();
+
+        match &mut self.nodes.get_mut(parent)?.kind {
+            NodeKind::Dir(children) => children.push(id),
+            NodeKind::File(_) => return None,
+        }
+
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(parent),
+            kind,
+        });
 
-        match parts[..] {
-            ["$", "cd", dst] => {
-                if dst == ".." {
-                    path.pop()?;
-                } else {
-                    path.push(dst);
+        Some(id)
+    }
+
+    /// Returns the directory `name` under `parent`, creating it if needed.
+    /// Fails if `parent` isn't a directory or `name` is already a file.
+    pub fn add_dir(&mut self, parent: NodeId, name: &str) -> Option<NodeId> {
+        match self.child(parent, name) {
+            Some(id) if self.nodes[id].is_dir() => Some(id),
+            Some(_) => None,
+            None => self.add(parent, name, NodeKind::Dir(Vec::new())),
+        }
+    }
+
+    /// Adds the file `name` under `parent`, or updates its size if it's
+    /// already there. Fails if `parent` isn't a directory or `name` is
+    /// already a directory.
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: usize) -> Option<NodeId> {
+        match self.child(parent, name) {
+            Some(id) => match &mut self.nodes[id].kind {
+                NodeKind::File(s) => {
+                    *s = size;
+                    Some(id)
                 }
+                NodeKind::Dir(_) => None,
+            },
+            None => self.add(parent, name, NodeKind::File(size)),
+        }
+    }
+
+    /// Looks up a `/`-separated path, relative to the root.
+    pub fn find(&self, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .filter(|s| !s.is_empty())
+            .try_fold(Self::ROOT, |id, name| self.child(id, name))
+    }
+
+    /// The absolute path of a node.
+    pub fn path(&self, id: NodeId) -> Option<String> {
+        let mut names = Vec::new();
+        let mut cur = id;
+
+        while let Some(parent) = self.parent(cur) {
+            names.push(self.nodes[cur].name.as_str());
+            cur = parent;
+        }
+
+        self.node(id)?;
+        names.reverse();
+
+        Some(format!("/{}", names.join("/")))
+    }
+
+    /// Visits every node depth-first, parents before children, yielding each
+    /// node's id along with its depth below the root.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            tree: self,
+            stack: vec![(Self::ROOT, 0)],
+        }
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|&id| self.nodes[id].is_dir())
+    }
+
+    /// Total size of every node, indexed by node id.
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.nodes.len()];
+
+        for (id, node) in self.nodes.iter().enumerate().rev() {
+            if let NodeKind::File(size) = node.kind {
+                sizes[id] += size;
             }
-            // The command itself isn't really useful. Just inspect the output
-            // instead.
-            ["$", "ls"] => continue,
-            [s, _] => {
-                if s == "dir" {
-                    continue;
-                }
 
-                let size = s.parse::<usize>().ok()?;
-                for i in 0..path.len() {
-                    let dir = path[..=i].join("/");
+            if let Some(parent) = node.parent {
+                sizes[parent] += sizes[id];
+            }
+        }
+
+        sizes
+    }
+
+    /// Draws the tree the way the puzzle does, one node per line.
+    pub fn render(&self) -> String {
+        let mut res = String::new();
+
+        for (id, depth) in self.walk() {
+            let node = &self.nodes[id];
+            let desc = match node.kind {
+                NodeKind::Dir(_) => String::from("dir"),
+                NodeKind::File(size) => format!("file, size={}", size),
+            };
 
-                    *dirs.entry(dir).or_default() += size;
+            res.push_str(&format!(
+                "{}- {} ({})\n",
+                "  ".repeat(depth),
+                node.name,
+                desc
+            ));
+        }
+
+        res
+    }
+
+    /// Lists every directory with its human-readable total size, largest
+    /// first, like `du -h | sort -rh`.
+    pub fn du(&self) -> String {
+        let sizes = self.sizes();
+        let mut dirs = self
+            .dirs()
+            .filter_map(|id| Some((sizes[id], self.path(id)?)))
+            .collect::<Vec<_>>();
+
+        dirs.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        dirs.iter()
+            .map(|(size, path)| format!("{}\t{}\n", human_size(*size), path))
+            .collect()
+    }
+
+    /// Writes a transcript that lists every directory once, descending into
+    /// subdirectories in order and climbing back out with `cd ..`.
+    pub fn to_transcript(&self) -> String {
+        let mut res = String::from("$ cd /\n");
+
+        self.write_transcript(Self::ROOT, &mut res);
+        res
+    }
+
+    fn write_transcript(&self, dir: NodeId, res: &mut String) {
+        res.push_str("$ ls\n");
+
+        for &c in self.children(dir) {
+            match self.nodes[c].kind {
+                NodeKind::Dir(_) => res.push_str(&format!("dir {}\n", self.nodes[c].name)),
+                NodeKind::File(size) => res.push_str(&format!("{} {}\n", size, self.nodes[c].name)),
+            }
+        }
+
+        for &c in self.children(dir) {
+            if self.nodes[c].is_dir() {
+                res.push_str(&format!("$ cd {}\n", self.nodes[c].name));
+                self.write_transcript(c, res);
+                res.push_str("$ cd ..\n");
+            }
+        }
+    }
+
+    /// Builds an arbitrary tree, the same one for a given seed and
+    /// parameters.
+    pub fn random(seed: u64, params: &GenParams) -> Self {
+        let mut tree = Self::new();
+        let mut rng = SplitMix64(seed);
+
+        tree.grow(Self::ROOT, 0, params, &mut rng);
+        tree
+    }
+
+    fn grow(&mut self, dir: NodeId, depth: usize, params: &GenParams, rng: &mut SplitMix64) {
+        let nr_children = rng.below(params.max_children + 1);
+        let mut dirs = Vec::new();
+
+        // add every child before descending, the order a transcript lists them in
+        for i in 0..nr_children {
+            if depth < params.max_depth && rng.below(3) == 0 {
+                dirs.push(self.add(dir, &format!("d{}", i), NodeKind::Dir(Vec::new())));
+            } else {
+                let size = rng.below(params.max_file_size) + 1;
+
+                self.add(dir, &format!("f{}.txt", i), NodeKind::File(size));
+            }
+        }
+
+        for id in dirs.into_iter().flatten() {
+            self.grow(id, depth + 1, params, rng);
+        }
+    }
+
+    /// Resolves a `cd` argument from `cwd`, creating directories that haven't
+    /// been listed yet.
+    fn change_dir(&mut self, cwd: NodeId, path: &str) -> Result<NodeId, &'static str> {
+        let start = if path.starts_with('/') {
+            Self::ROOT
+        } else {
+            cwd
+        };
+
+        path.split('/')
+            .filter(|s| !s.is_empty() && *s != ".")
+            .try_fold(start, |id, name| match name {
+                ".." => self.parent(id).ok_or("no parent directory"),
+                _ => self.add_dir(id, name).ok_or("not a directory"),
+            })
+    }
+
+    /// Replays a terminal transcript of `cd` and `ls` commands.
+    ///
+    /// Listing a directory again updates it rather than adding its files a
+    /// second time.
+    pub fn from_transcript(input: &str) -> Result<Self, ParseError> {
+        let mut tree = Self::new();
+        let mut cwd = Self::ROOT;
+        let mut listing = false;
+
+        for (i, line) in input.lines().enumerate() {
+            let err = |reason| ParseError {
+                line: i + 1,
+                reason,
+            };
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+
+            match parts[..] {
+                ["$", "cd", path] => {
+                    cwd = tree.change_dir(cwd, path).map_err(err)?;
+                    listing = false;
                 }
+                ["$", "cd", ..] => return Err(err("cd expects a single path")),
+                ["$", "ls"] => listing = true,
+                ["$", "ls", ..] => return Err(err("ls takes no arguments")),
+                ["$", ..] => return Err(err("unknown command")),
+                _ if !listing => return Err(err("output outside of ls")),
+                ["dir", name] => {
+                    tree.add_dir(cwd, name)
+                        .ok_or_else(|| err("not a directory"))?;
+                }
+                [s, name] => {
+                    let size = s.parse::<usize>().or(Err(err("couldn't parse size")))?;
+
+                    tree.add_file(cwd, name, size)
+                        .ok_or_else(|| err("is a directory"))?;
+                }
+                _ => return Err(err("malformed ls output")),
             }
-            _ => return None,
+        }
+
+        Ok(tree)
+    }
+}
+
+impl FromStr for FsTree {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_transcript(s)
+    }
+}
+
+/// Formats a size the way `du -h` does: powers of 1024, rounded up, with
+/// one decimal below 10.
+fn human_size(size: usize) -> String {
+    const UNITS: [&str; 5] = ["", "K", "M", "G", "T"];
+    let mut value = size as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        size.to_string()
+    } else if value < 10.0 {
+        format!("{:.1}{}", (value * 10.0).ceil() / 10.0, UNITS[unit])
+    } else {
+        format!("{}{}", value.ceil(), UNITS[unit])
+    }
+}
+
+pub struct Walk<'a> {
+    tree: &'a FsTree,
+    stack: Vec<(NodeId, usize)>,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = (NodeId, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, depth) = self.stack.pop()?;
+
+        self.stack
+            .extend(self.tree.children(id).iter().rev().map(|&c| (c, depth + 1)));
+
+        Some((id, depth))
+    }
+}
+
+/// Shape of the trees built by [`FsTree::random`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GenParams {
+    pub max_depth: usize,
+    pub max_children: usize,
+    pub max_file_size: usize,
+}
+
+impl Default for GenParams {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            max_children: 6,
+            max_file_size: 300_000,
         }
     }
+}
+
+/// Small, seedable generator; good enough for shaping test trees.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
 
-    Some(dirs)
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A value in `0..n`, or 0 if `n` is 0.
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next() % n as u64) as usize
+        }
+    }
 }
 
 pub fn part1(input: &str) -> Option<usize> {
-    let dirs = compute_dirs(input)?;
+    let tree = FsTree::from_transcript(input).ok()?;
+    let sizes = tree.sizes();
 
-    Some(dirs.into_values().filter(|size| *size <= 100_000).sum())
+    Some(
+        tree.dirs()
+            .map(|id| sizes[id])
+            .filter(|size| *size <= 100_000)
+            .sum(),
+    )
 }
 
 const DISK_SIZE: usize = 70_000_000;
 const SPACE_REQUIRED: usize = 30_000_000;
 
-pub fn part2(input: &str) -> Option<usize> {
-    let dirs = compute_dirs(input)?;
-    let used = *dirs.get("/")?;
-    let available = DISK_SIZE.checked_sub(used)?;
-    let space_required = SPACE_REQUIRED.checked_sub(available)?;
+/// Picks directories to delete so that a disk of `disk_size` ends up with at
+/// least `space_required` free.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CleanupPlanner {
+    pub disk_size: usize,
+    pub space_required: usize,
+}
+
+impl Default for CleanupPlanner {
+    fn default() -> Self {
+        Self::new(DISK_SIZE, SPACE_REQUIRED)
+    }
+}
+
+impl CleanupPlanner {
+    pub fn new(disk_size: usize, space_required: usize) -> Self {
+        Self {
+            disk_size,
+            space_required,
+        }
+    }
+
+    /// How much has to be deleted. `None` if the tree doesn't even fit on
+    /// the disk, or `sizes` is empty.
+    pub fn space_to_free(&self, sizes: &[usize]) -> Option<usize> {
+        let available = self.disk_size.checked_sub(*sizes.get(FsTree::ROOT)?)?;
+
+        Some(self.space_required.saturating_sub(available))
+    }
 
-    println!("{}", space_required);
+    /// The smallest single directory that frees enough space. `None` if no
+    /// directory is large enough, or nothing needs deleting.
+    pub fn smallest_dir(&self, tree: &FsTree) -> Option<NodeId> {
+        let sizes = tree.sizes();
+        let needed = self.space_to_free(&sizes)?;
+
+        if needed == 0 {
+            return None;
+        }
+
+        tree.dirs()
+            .filter(|&id| sizes[id] >= needed)
+            .min_by_key(|&id| sizes[id])
+    }
+
+    /// The set of directories, none inside another, that frees enough space
+    /// while deleting as little as possible.
+    ///
+    /// Knapsack over the directories in walk order, one row of reachable
+    /// totals per open directory.
+    /// Rather than keeping every step around to retrace the answer, the
+    /// directories are recovered one by one: the one whose deletion first
+    /// made the remaining total reachable is part of a set adding up to it.
+    pub fn best_set(&self, tree: &FsTree) -> Option<Vec<NodeId>> {
+        let sizes = tree.sizes();
+        let needed = self.space_to_free(&sizes)?;
+
+        if needed == 0 {
+            return Some(Vec::new());
+        }
 
-    dirs.into_values()
-        .filter(|size| *size >= space_required)
-        .min()
+        // the smallest single directory bounds the answer
+        let bound = sizes[self.smallest_dir(tree)?];
+        let reachable = deletable_totals(tree, &sizes, bound, |_, _| false);
+        let mut total = (needed..=bound).find(|&t| has_bit(&reachable, t))?;
+        let mut set = Vec::new();
+
+        while total > 0 {
+            let mut dir = None;
+
+            deletable_totals(tree, &sizes, total, |id, reachable| {
+                dir = Some(id);
+                has_bit(reachable, total)
+            });
+            set.push(dir?);
+            total -= sizes[dir?];
+        }
+
+        Some(set)
+    }
+}
+
+fn has_bit(bits: &[u64], i: usize) -> bool {
+    bits[i / 64] & (1 << (i % 64)) != 0
+}
+
+/// The totals up to `bound` that deleting whole directories, none inside
+/// another, can add up to.
+///
+/// Each directory in walk order is either deleted, skipping everything below
+/// it, or kept so that its subdirectories get a say. A deletion takes effect
+/// once the walk leaves the directory, so only one set of totals per open
+/// directory is kept. `settled` sees each deletion's directory along with the
+/// totals reachable right after it takes effect, and can stop the walk there
+/// by returning `true`.
+fn deletable_totals<F>(tree: &FsTree, sizes: &[usize], bound: usize, mut settled: F) -> Vec<u64>
+where
+    F: FnMut(NodeId, &[u64]) -> bool,
+{
+    let words = bound / 64 + 1;
+    let mut reachable = vec![0u64; words];
+    let mut pending: Vec<(usize, NodeId, Vec<u64>)> = Vec::new();
+    let dirs = tree.walk().filter(|&(id, _)| tree.nodes[id].is_dir());
+
+    reachable[0] = 1;
+
+    // a trailing depth of 0 closes every directory still open
+    for (id, depth) in dirs.map(|(id, d)| (Some(id), d)).chain([(None, 0)]) {
+        while pending.last().is_some_and(|&(d, _, _)| d >= depth) {
+            let (_, dir, row) = pending.pop().unwrap();
+
+            or_shifted(&mut reachable, &row, 0);
+            if settled(dir, &reachable) {
+                return reachable;
+            }
+        }
+
+        if let Some(id) = id.filter(|&id| sizes[id] <= bound) {
+            let mut row = vec![0u64; words];
+
+            or_shifted(&mut row, &reachable, sizes[id]);
+            pending.push((depth, id, row));
+        }
+    }
+
+    reachable
+}
+
+/// ORs `src`, shifted up by `shift` bits, into `dst`. Bits shifted past the
+/// end of `dst` are dropped.
+fn or_shifted(dst: &mut [u64], src: &[u64], shift: usize) {
+    let (words, bits) = (shift / 64, shift % 64);
+
+    for i in (words..dst.len()).rev() {
+        let j = i - words;
+        let mut v = src[j] << bits;
+
+        if bits > 0 && j > 0 {
+            v |= src[j - 1] >> (64 - bits);
+        }
+
+        dst[i] |= v;
+    }
+}
+
+pub fn part2(input: &str) -> Option<usize> {
+    let tree = FsTree::from_transcript(input).ok()?;
+    let dir = CleanupPlanner::default().smallest_dir(&tree)?;
+
+    Some(tree.sizes()[dir])
 }
 
 #[cfg(test)]
@@ -85,4 +610,226 @@ mod tests {
     fn part2_test() {
         assert_eq!(part2(TEST), Some(3579501))
     }
+
+    #[test]
+    fn tree_queries() {
+        let tree = FsTree::from_transcript(EXAMPLE).unwrap();
+        let sizes = tree.sizes();
+        let e = tree.find("/a/e").unwrap();
+
+        assert_eq!(tree.walk().count(), 14);
+        assert_eq!(sizes[e], 584);
+        assert_eq!(sizes[tree.find("a").unwrap()], 94853);
+        assert_eq!(sizes[FsTree::ROOT], 48381165);
+        assert_eq!(tree.path(e), Some(String::from("/a/e")));
+        assert_eq!(tree.parent(e), tree.find("/a"));
+        assert_eq!(tree.find("/a/x"), None);
+        assert_eq!(tree.find("/b.txt/x"), None);
+
+        let names = tree
+            .children(FsTree::ROOT)
+            .iter()
+            .map(|&c| tree.node(c).unwrap().name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b.txt", "c.dat", "d"]);
+
+        let walk = tree
+            .walk()
+            .map(|(id, depth)| (tree.node(id).unwrap().name.as_str(), depth))
+            .take(5)
+            .collect::<Vec<_>>();
+        assert_eq!(walk, [("/", 0), ("a", 1), ("e", 2), ("i", 3), ("f", 2)]);
+    }
+
+    #[test]
+    fn transcript_paths() {
+        let transcript = "$ cd /a/b\n$ ls\n10 x\n$ cd /\n$ cd a/./b/../c\n$ ls\n20 y\n$ cd ../../a/b\n$ ls\n10 x\n15 z\n";
+        let tree = FsTree::from_transcript(transcript).unwrap();
+        let sizes = tree.sizes();
+
+        assert_eq!(sizes[tree.find("/a/b").unwrap()], 25);
+        assert_eq!(sizes[tree.find("/a/c").unwrap()], 20);
+        assert_eq!(sizes[FsTree::ROOT], 45);
+    }
+
+    #[test]
+    fn transcript_relist() {
+        let relisted = EXAMPLE.replace(
+            "$ cd ..\n$ cd ..\n",
+            "$ cd ..\n$ ls\ndir e\n29116 f\n2557 g\n62596 h.lst\n$ cd ..\n",
+        );
+
+        assert_ne!(relisted, EXAMPLE);
+        assert_eq!(part1(&relisted), Some(95437));
+        assert_eq!(part2(&relisted), Some(24933642));
+    }
+
+    #[test]
+    fn transcript_errors() {
+        let err = |line, reason| Err(ParseError { line, reason });
+
+        assert_eq!(
+            FsTree::from_transcript("$ cd /\n$ rm -rf a\n"),
+            err(2, "unknown command")
+        );
+        assert_eq!(
+            FsTree::from_transcript("$ cd /\n$ cd ..\n"),
+            err(2, "no parent directory")
+        );
+        assert_eq!(
+            FsTree::from_transcript("$ ls\n10 a\n$ cd a\n"),
+            err(3, "not a directory")
+        );
+        assert_eq!(
+            FsTree::from_transcript("$ ls\ndir a\n$ cd a\n10 b\n"),
+            err(4, "output outside of ls")
+        );
+        assert_eq!(
+            FsTree::from_transcript("$ ls\nten a\n"),
+            err(2, "couldn't parse size")
+        );
+        assert_eq!(
+            FsTree::from_transcript("$ ls\ndir a\n10 a\n"),
+            err(3, "is a directory")
+        );
+    }
+
+    #[test]
+    fn render_tree() {
+        let tree = FsTree::from_transcript(EXAMPLE).unwrap();
+
+        assert_eq!(
+            tree.render(),
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)
+"
+        );
+    }
+
+    #[test]
+    fn render_du() {
+        let tree = FsTree::from_transcript(EXAMPLE).unwrap();
+
+        assert_eq!(tree.du(), "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1025), "1.1K");
+        assert_eq!(human_size(10 * 1024 * 1024), "10M");
+    }
+
+    #[test]
+    fn cleanup_single() {
+        let tree = FsTree::from_transcript(EXAMPLE).unwrap();
+
+        assert_eq!(
+            CleanupPlanner::new(70_000_000, 30_000_000).smallest_dir(&tree),
+            tree.find("/d")
+        );
+        assert_eq!(
+            CleanupPlanner::new(48_400_000, 100_000).smallest_dir(&tree),
+            tree.find("/a")
+        );
+        assert_eq!(
+            CleanupPlanner::new(90_000_000, 30_000_000).smallest_dir(&tree),
+            None
+        );
+        assert_eq!(CleanupPlanner::new(40_000_000, 0).smallest_dir(&tree), None);
+    }
+
+    #[test]
+    fn cleanup_set() {
+        let tree = FsTree::from_transcript(
+            "$ ls\ndir a\ndir b\ndir c\n$ cd a\n$ ls\n60 x\n$ cd ../b\n$ ls\n50 y\ndir d\n$ cd d\n$ ls\n45 z\n$ cd /c\n$ ls\n40 w\n",
+        )
+        .unwrap();
+        let sizes = tree.sizes();
+        let planner = CleanupPlanner::new(195, 80);
+        let single = planner.smallest_dir(&tree).unwrap();
+        let mut set = planner.best_set(&tree).unwrap();
+        set.sort();
+
+        assert_eq!(sizes[single], 95);
+        assert_eq!(set, [tree.find("/c").unwrap(), tree.find("/b/d").unwrap()]);
+        assert_eq!(set.iter().map(|&id| sizes[id]).sum::<usize>(), 85);
+
+        assert_eq!(
+            CleanupPlanner::new(195, 0).best_set(&tree),
+            Some(Vec::new())
+        );
+        assert_eq!(CleanupPlanner::new(100, 0).best_set(&tree), None);
+        assert_eq!(CleanupPlanner::default().space_to_free(&[]), None);
+    }
+
+    #[test]
+    fn cleanup_set_test() {
+        let tree = FsTree::from_transcript(TEST).unwrap();
+        let sizes = tree.sizes();
+        let planner = CleanupPlanner::default();
+        let needed = planner.space_to_free(&sizes).unwrap();
+        let set = planner.best_set(&tree).unwrap();
+        let freed = set.iter().map(|&id| sizes[id]).sum::<usize>();
+
+        // an exact fit exists for this input
+        assert_eq!(freed, needed);
+
+        for &id in &set {
+            let mut cur = id;
+
+            while let Some(parent) = tree.parent(cur) {
+                assert!(!set.contains(&parent));
+                cur = parent;
+            }
+        }
+    }
+
+    #[test]
+    fn transcript_round_trip() {
+        for input in [EXAMPLE, TEST] {
+            let tree = FsTree::from_transcript(input).unwrap();
+            let replayed = FsTree::from_transcript(&tree.to_transcript()).unwrap();
+
+            assert_eq!(replayed.render(), tree.render());
+        }
+
+        for seed in 0..50 {
+            let tree = FsTree::random(seed, &GenParams::default());
+            let transcript = tree.to_transcript();
+
+            assert_eq!(FsTree::from_transcript(&transcript), Ok(tree));
+        }
+    }
+
+    #[test]
+    fn random_tree_params() {
+        let params = GenParams {
+            max_depth: 2,
+            max_children: 3,
+            max_file_size: 10,
+        };
+
+        for seed in 0..50 {
+            let tree = FsTree::random(seed, &params);
+
+            assert_eq!(tree, FsTree::random(seed, &params));
+            assert!(tree.walk().all(|(id, depth)| {
+                tree.children(id).len() <= 3
+                    && depth <= 3
+                    && match tree.node(id).unwrap().kind {
+                        NodeKind::File(size) => (1..=10).contains(&size),
+                        NodeKind::Dir(_) => depth <= 2,
+                    }
+            }));
+        }
+    }
 }
@@ -1,14 +1,95 @@
+use crate::error::ParseError;
+use crate::grid::Grid;
 use std::collections::HashSet;
+use std::str::FromStr;
 
-fn get_grid(input: &str) -> Option<Vec<Vec<u32>>> {
-    input
-        .lines()
-        .map(|l| {
-            l.chars()
-                .map(|c| c.to_digit(10))
-                .collect::<Option<Vec<_>>>()
+/// How the heights on each line of a height map are written.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HeightFormat {
+    /// One digit per tree, as in the puzzle input.
+    #[default]
+    Digits,
+    /// Whitespace-separated numbers of any width.
+    Separated,
+}
+
+/// Parses a height map, one row per line, every line written in `format`.
+///
+/// With `allow_ragged`, rows may differ in length and the cells missing from
+/// short rows are `None`; otherwise every row must match the first one.
+pub fn parse_heights(
+    input: &str,
+    format: HeightFormat,
+    allow_ragged: bool,
+) -> Result<Grid<Option<u32>>, ParseError> {
+    let mut rows = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let err = |reason| ParseError {
+            line: i + 1,
+            reason,
+        };
+        let row = match format {
+            HeightFormat::Digits => line.trim().chars().map(|c| c.to_digit(10)).collect(),
+            HeightFormat::Separated => line
+                .split_whitespace()
+                .map(|h| h.parse::<u32>().ok())
+                .collect::<Option<Vec<_>>>(),
+        }
+        .ok_or_else(|| err("invalid height"))?;
+
+        if !allow_ragged && rows.first().is_some_and(|r: &Vec<_>| r.len() != row.len()) {
+            return Err(err("row length differs from the first row"));
+        }
+
+        rows.push(row);
+    }
+
+    let cols = rows.iter().map(Vec::len).max().unwrap_or_default();
+    let rows = rows
+        .into_iter()
+        .map(|r| {
+            let mut cells = r.into_iter().map(Some).collect::<Vec<_>>();
+
+            cells.resize(cols, None);
+            cells
         })
-        .collect::<Option<Vec<Vec<_>>>>()
+        .collect();
+
+    Grid::from_rows(rows).ok_or(ParseError {
+        line: 1,
+        reason: "inconsistent rows",
+    })
+}
+
+/// Parses a rectangular map of single-digit heights.
+pub fn get_grid(input: &str) -> Option<Grid<u32>> {
+    let heights = parse_heights(input, HeightFormat::Digits, false).ok()?;
+    let cells = heights
+        .iter()
+        .map(|(_, &h)| h)
+        .collect::<Option<Vec<_>>>()?;
+
+    Grid::new(heights.rows(), heights.cols(), cells)
+}
+
+/// Splits a line of cells into runs of consecutive trees, pairing each tree
+/// with its index in the line. Missing cells act as the edge of the forest.
+fn __runs<'a, I>(cells: I) -> Vec<Vec<(usize, &'a u32)>>
+where
+    I: Iterator<Item = &'a Option<u32>>,
+{
+    let mut runs = vec![Vec::new()];
+
+    for (i, cell) in cells.enumerate() {
+        match cell {
+            Some(h) => runs.last_mut().unwrap().push((i, h)),
+            None => runs.push(Vec::new()),
+        }
+    }
+
+    runs.retain(|r| !r.is_empty());
+    runs
 }
 
 fn __find_visible<'a, I>(trees: I, res: &mut HashSet<u32>)
@@ -26,36 +107,149 @@ where
     }
 }
 
-fn find_visible(trees: &[u32]) -> HashSet<u32> {
-    let mut res = HashSet::new();
+/// The edges of the forest a tree can be seen from, as a set of flags.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Edges(u8);
 
-    __find_visible(trees.iter().enumerate(), &mut res);
-    __find_visible(trees.iter().enumerate().rev(), &mut res);
+impl Edges {
+    pub const NONE: Edges = Edges(0);
+    pub const TOP: Edges = Edges(1);
+    pub const BOTTOM: Edges = Edges(2);
+    pub const LEFT: Edges = Edges(4);
+    pub const RIGHT: Edges = Edges(8);
 
-    res
+    pub fn contains(self, other: Edges) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Edges) {
+        self.0 |= other.0;
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
 }
 
-pub fn part1(input: &str) -> Option<usize> {
-    let mut visible = HashSet::new();
-    let grid = get_grid(input)?;
-    let nr_cols = grid.get(0)?.len();
+fn __mark_visible<F>(map: &mut Grid<Edges>, found: &mut HashSet<u32>, edge: Edges, at: F)
+where
+    F: Fn(usize) -> (usize, usize),
+{
+    for i in found.drain() {
+        map[at(i as usize)].insert(edge);
+    }
+}
+
+/// Which edges every tree is visible from.
+pub fn visibility_map(grid: &Grid<u32>) -> Grid<Edges> {
+    sparse_visibility_map(&grid.map(|&h| Some(h)))
+}
+
+/// Like [`visibility_map`], for forests with missing cells.
+pub fn sparse_visibility_map(grid: &Grid<Option<u32>>) -> Grid<Edges> {
+    let mut map = grid.map(|_| Edges::NONE);
+    let mut found = HashSet::new();
+
+    for c in 0..grid.cols() {
+        let Some(col) = grid.column(c) else { continue };
+
+        for run in __runs(col) {
+            __find_visible(run.iter().copied(), &mut found);
+            __mark_visible(&mut map, &mut found, Edges::TOP, |r| (r, c));
+            __find_visible(run.iter().rev().copied(), &mut found);
+            __mark_visible(&mut map, &mut found, Edges::BOTTOM, |r| (r, c));
+        }
+    }
 
-    // test visibility from top and bottom
-    for col_idx in 0..nr_cols {
-        let col: Vec<u32> = grid.iter().map(|r| r[col_idx]).collect();
+    for r in 0..grid.rows() {
+        let Some(row) = grid.row(r) else { continue };
 
-        for row_idx in find_visible(&col) {
-            visible.insert((row_idx, col_idx));
+        for run in __runs(row.iter()) {
+            __find_visible(run.iter().copied(), &mut found);
+            __mark_visible(&mut map, &mut found, Edges::LEFT, |c| (r, c));
+            __find_visible(run.iter().rev().copied(), &mut found);
+            __mark_visible(&mut map, &mut found, Edges::RIGHT, |c| (r, c));
         }
     }
 
-    for (row_idx, row) in grid.iter().enumerate() {
-        for col_idx in find_visible(row) {
-            visible.insert((row_idx as u32, col_idx as usize));
+    map
+}
+
+/// Draws the visibility map, one character per tree: `.` for hidden trees,
+/// otherwise the hex digit of its [`Edges`] flags (`1` top, `2` bottom, `4`
+/// left, `8` right).
+pub fn render_visibility(map: &Grid<Edges>) -> String {
+    let mut res = String::new();
+
+    for r in 0..map.rows() {
+        for edges in map.row(r).unwrap_or_default() {
+            res.push(match edges.bits() {
+                0 => '.',
+                b => char::from_digit(u32::from(b), 16).unwrap_or('?'),
+            });
+        }
+        res.push('\n');
+    }
+
+    res
+}
+
+/// Hidden trees are dark; visible ones get brighter the more edges they're
+/// seen from.
+pub fn visibility_image(map: &Grid<Edges>) -> Grid<[u8; 3]> {
+    map.map(|edges| match edges.count() {
+        0 => [16, 48, 16],
+        n => [40 * n as u8, 60 + 48 * n as u8, 40 * n as u8],
+    })
+}
+
+/// Position of a score between 0 and the highest one, on a log scale since
+/// a handful of trees dwarf the rest.
+fn heat(score: usize, max: usize) -> f64 {
+    if max == 0 {
+        0.0
+    } else {
+        (score as f64).ln_1p() / (max as f64).ln_1p()
+    }
+}
+
+const HEAT_RAMP: &[u8] = b" .:-=+*#%@";
+
+/// Draws scenic scores as an ASCII heatmap, from ` ` (0) to `@` (highest).
+pub fn render_heatmap(scores: &Grid<usize>) -> String {
+    let max = scores.iter().map(|(_, &s)| s).max().unwrap_or_default();
+    let mut res = String::new();
+
+    for r in 0..scores.rows() {
+        for &score in scores.row(r).unwrap_or_default() {
+            let idx = (heat(score, max) * (HEAT_RAMP.len() - 1) as f64).round() as usize;
+
+            res.push(char::from(HEAT_RAMP[idx]));
         }
+        res.push('\n');
     }
 
-    Some(visible.len())
+    res
+}
+
+/// Scenic scores from black through red and yellow to white.
+pub fn heatmap_image(scores: &Grid<usize>) -> Grid<[u8; 3]> {
+    let max = scores.iter().map(|(_, &s)| s).max().unwrap_or_default();
+
+    scores.map(|&score| {
+        let v = heat(score, max) * 3.0;
+        let channel = |x: f64| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        [channel(v), channel(v - 1.0), channel(v - 2.0)]
+    })
 }
 
 fn __viewing_distance<'a, I>(height: u32, trees: I) -> usize
@@ -74,28 +268,233 @@ where
     distance
 }
 
-fn scenic_score(r: usize, c: usize, grid: &[Vec<u32>]) -> usize {
-    let col = grid.iter().map(|row| row[c]).collect::<Vec<_>>();
-    let height = grid[r][c];
-    let top = __viewing_distance(height, col[..r].iter().rev());
-    let bottom = __viewing_distance(height, col[r + 1..].iter());
-    let left = __viewing_distance(height, grid[r][..c].iter().rev());
-    let right = __viewing_distance(height, grid[r][c + 1..].iter());
+/// Scenic score of a single tree, scanning outwards from it.
+pub fn scenic_score(r: usize, c: usize, grid: &Grid<u32>) -> Option<usize> {
+    let height = *grid.get(r, c)?;
+    let row = grid.row(r)?;
+    let top = __viewing_distance(height, grid.column(c)?.take(r).rev());
+    let bottom = __viewing_distance(height, grid.column(c)?.skip(r + 1));
+    let left = __viewing_distance(height, row[..c].iter().rev());
+    let right = __viewing_distance(height, row[c + 1..].iter());
 
-    top * bottom * left * right
+    Some(top * bottom * left * right)
 }
 
-pub fn part2(input: &str) -> Option<usize> {
-    let grid = get_grid(input)?;
-    let mut scores = Vec::new();
+/// Adds tree `i` of a line to `stack` and returns how far it sees back
+/// towards the start of the line.
+///
+/// The stack holds the trees that could still block a later one, tallest at
+/// the bottom. Each tree is pushed and popped at most once per line.
+fn __viewing_distance_back(stack: &mut Vec<(usize, u32)>, i: usize, height: u32) -> usize {
+    while stack.last().is_some_and(|&(_, h)| h < height) {
+        stack.pop();
+    }
+
+    let distance = match stack.last() {
+        Some(&(j, _)) => i - j,
+        None => i,
+    };
+
+    stack.push((i, height));
+    distance
+}
+
+/// How many trees a tree sees towards each edge before its view is blocked.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Distances {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Distances {
+    pub fn scenic_score(&self) -> usize {
+        self.top * self.bottom * self.left * self.right
+    }
+}
+
+/// Feeds cell `i` of a line to [`__viewing_distance_back`], starting over
+/// after a missing cell. `start` is where the current run of trees began.
+fn __viewing_distance_run(
+    stack: &mut Vec<(usize, u32)>,
+    start: &mut usize,
+    i: usize,
+    cell: Option<u32>,
+) -> usize {
+    match cell {
+        Some(height) => __viewing_distance_back(stack, i - *start, height),
+        None => {
+            stack.clear();
+            *start = i + 1;
+            0
+        }
+    }
+}
+
+/// Viewing distances of every tree, in O(rows * cols).
+pub fn viewing_distances(grid: &Grid<u32>) -> Grid<Distances> {
+    sparse_viewing_distances(&grid.map(|&h| Some(h)))
+}
+
+/// Like [`viewing_distances`], for forests with missing cells. Missing
+/// cells get zero distances.
+pub fn sparse_viewing_distances(grid: &Grid<Option<u32>>) -> Grid<Distances> {
+    let (rows, cols) = (grid.rows(), grid.cols());
+    let mut res = grid.map(|_| Distances::default());
+    let mut stack = Vec::new();
+
+    for r in 0..rows {
+        let (mut start, mut start_rev) = (0, 0);
+
+        stack.clear();
+        for c in 0..cols {
+            res[(r, c)].left = __viewing_distance_run(&mut stack, &mut start, c, grid[(r, c)]);
+        }
+
+        stack.clear();
+        for c in (0..cols).rev() {
+            res[(r, c)].right =
+                __viewing_distance_run(&mut stack, &mut start_rev, cols - 1 - c, grid[(r, c)]);
+        }
+    }
+
+    // sweep whole rows at a time, keeping a stack per column
+    let mut stacks = vec![Vec::new(); cols];
+    let mut starts = vec![0; cols];
+
+    for r in 0..rows {
+        for (c, (stack, start)) in stacks.iter_mut().zip(&mut starts).enumerate() {
+            res[(r, c)].top = __viewing_distance_run(stack, start, r, grid[(r, c)]);
+        }
+    }
+
+    stacks.iter_mut().for_each(Vec::clear);
+    starts.fill(0);
+
+    for r in (0..rows).rev() {
+        for (c, (stack, start)) in stacks.iter_mut().zip(&mut starts).enumerate() {
+            res[(r, c)].bottom = __viewing_distance_run(stack, start, rows - 1 - r, grid[(r, c)]);
+        }
+    }
+
+    res
+}
+
+/// Scenic scores of every tree, in O(rows * cols).
+pub fn scenic_scores(grid: &Grid<u32>) -> Grid<usize> {
+    viewing_distances(grid).map(Distances::scenic_score)
+}
+
+/// Everything known about a single tree.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TreeView {
+    pub height: u32,
+    pub visible_from: Edges,
+    pub distances: Distances,
+}
+
+impl TreeView {
+    pub fn is_visible(&self) -> bool {
+        !self.visible_from.is_empty()
+    }
+
+    pub fn scenic_score(&self) -> usize {
+        self.distances.scenic_score()
+    }
+}
 
-    for (row_idx, row) in grid.iter().enumerate() {
-        for col_idx in 0..row.len() {
-            scores.push(scenic_score(row_idx, col_idx, &grid));
+/// A forest with every tree's visibility and viewing distances worked out
+/// up front, for answering per-tree questions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Forest {
+    trees: Grid<Option<TreeView>>,
+}
+
+impl Forest {
+    pub fn new(grid: &Grid<u32>) -> Self {
+        Self::from_heights(&grid.map(|&h| Some(h)))
+    }
+
+    /// Builds a forest that may have missing cells, which count as being
+    /// outside of it.
+    pub fn from_heights(grid: &Grid<Option<u32>>) -> Self {
+        let visibility = sparse_visibility_map(grid);
+        let distances = sparse_viewing_distances(grid);
+        let mut trees = grid.map(|&h| {
+            h.map(|height| TreeView {
+                height,
+                ..Default::default()
+            })
+        });
+
+        for r in 0..grid.rows() {
+            for c in 0..grid.cols() {
+                if let Some(tree) = &mut trees[(r, c)] {
+                    tree.visible_from = visibility[(r, c)];
+                    tree.distances = distances[(r, c)];
+                }
+            }
         }
+
+        Self { trees }
+    }
+
+    /// The tree at `(r, c)`, or `None` if that's outside the forest.
+    pub fn tree(&self, r: usize, c: usize) -> Option<&TreeView> {
+        self.trees.get(r, c)?.as_ref()
+    }
+
+    pub fn visible_from(&self, r: usize, c: usize) -> Option<Edges> {
+        Some(self.tree(r, c)?.visible_from)
+    }
+
+    pub fn trees(&self) -> impl Iterator<Item = ((usize, usize), &TreeView)> {
+        self.trees
+            .iter()
+            .filter_map(|(pos, t)| Some((pos, t.as_ref()?)))
+    }
+
+    pub fn nr_visible(&self) -> usize {
+        self.trees().filter(|(_, t)| t.is_visible()).count()
+    }
+
+    /// The `n` trees with the highest scenic scores, best first. Ties go to
+    /// the tree that comes first reading row by row.
+    pub fn top_scenic(&self, n: usize) -> Vec<((usize, usize), usize)> {
+        let mut scores = self
+            .trees()
+            .map(|(pos, t)| (pos, t.scenic_score()))
+            .collect::<Vec<_>>();
+
+        scores.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores.truncate(n);
+        scores
     }
+}
+
+impl FromStr for Forest {
+    type Err = ParseError;
+
+    /// Parses a rectangular map of single-digit heights. Other maps go
+    /// through [`parse_heights`] and [`Forest::from_heights`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from_heights(&parse_heights(
+            s,
+            HeightFormat::Digits,
+            false,
+        )?))
+    }
+}
 
-    scores.iter().max().copied()
+pub fn part1(input: &str) -> Option<usize> {
+    Some(input.parse::<Forest>().ok()?.nr_visible())
+}
+
+pub fn part2(input: &str) -> Option<usize> {
+    let forest = input.parse::<Forest>().ok()?;
+
+    forest.top_scenic(1).first().map(|&(_, score)| score)
 }
 
 #[cfg(test)]
@@ -124,4 +523,163 @@ mod tests {
     fn part2_test() {
         assert_eq!(part2(TEST), Some(496650))
     }
+
+    #[test]
+    fn scenic_scores_match_per_tree() {
+        for input in [EXAMPLE, TEST] {
+            let grid = get_grid(input).unwrap();
+            let scores = scenic_scores(&grid);
+
+            for ((r, c), &score) in scores.iter() {
+                assert_eq!(Some(score), scenic_score(r, c, &grid));
+            }
+        }
+    }
+
+    #[test]
+    fn visibility() {
+        let grid = get_grid(EXAMPLE).unwrap();
+        let map = visibility_map(&grid);
+
+        assert_eq!(
+            render_visibility(&map),
+            "511d9\n459.8\nf8.88\n4.6.f\n662fa\n"
+        );
+        assert!(map[(1, 1)].contains(Edges::TOP));
+        assert!(map[(1, 1)].contains(Edges::LEFT));
+        assert!(!map[(1, 1)].contains(Edges::RIGHT));
+        assert_eq!(map.iter().filter(|(_, e)| !e.is_empty()).count(), 21);
+
+        let map = visibility_map(&get_grid(TEST).unwrap());
+        assert_eq!(map.iter().filter(|(_, e)| !e.is_empty()).count(), 1703);
+    }
+
+    #[test]
+    fn heatmap() {
+        let scores = scenic_scores(&get_grid(EXAMPLE).unwrap());
+        let ascii = render_heatmap(&scores);
+
+        assert_eq!(ascii.lines().count(), 5);
+        assert_eq!(ascii.chars().filter(|&c| c == '@').count(), 1);
+        assert_eq!(ascii.lines().nth(3).unwrap().chars().nth(2), Some('@'));
+        assert_eq!(ascii.lines().next(), Some("     "));
+
+        let image = heatmap_image(&scores);
+        assert_eq!(image[(3, 2)], [255, 255, 255]);
+        assert_eq!(image[(0, 0)], [0, 0, 0]);
+    }
+
+    #[test]
+    fn forest_queries() {
+        let forest = EXAMPLE.parse::<Forest>().unwrap();
+        let tree = forest.tree(3, 2).unwrap();
+
+        assert_eq!(tree.height, 5);
+        assert_eq!(
+            tree.distances,
+            Distances {
+                top: 2,
+                bottom: 1,
+                left: 2,
+                right: 2
+            }
+        );
+        assert_eq!(tree.visible_from, Edges(Edges::LEFT.0 | Edges::BOTTOM.0));
+
+        let edges = forest.visible_from(1, 2).unwrap();
+        assert!(edges.contains(Edges::TOP) && edges.contains(Edges::RIGHT));
+        assert!(!edges.contains(Edges::LEFT) && !edges.contains(Edges::BOTTOM));
+        assert_eq!(forest.visible_from(5, 0), None);
+
+        assert_eq!(
+            forest.top_scenic(3),
+            [((3, 2), 8), ((2, 1), 6), ((1, 2), 4)]
+        );
+    }
+
+    #[test]
+    fn multi_digit_heights() {
+        let heights = parse_heights("10 2 30\n4  50 6\n", HeightFormat::Separated, false).unwrap();
+        let forest = Forest::from_heights(&heights);
+
+        assert_eq!(heights[(1, 1)], Some(50));
+        assert_eq!(forest.tree(1, 1).unwrap().height, 50);
+        assert_eq!(forest.nr_visible(), 6);
+        assert_eq!(
+            parse_heights("10 2 x\n", HeightFormat::Separated, false),
+            Err(ParseError {
+                line: 1,
+                reason: "invalid height"
+            })
+        );
+
+        let spaced = EXAMPLE
+            .lines()
+            .map(|l| l.chars().map(String::from).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            parse_heights(&spaced, HeightFormat::Separated, false),
+            parse_heights(EXAMPLE, HeightFormat::Digits, false)
+        );
+    }
+
+    #[test]
+    fn one_format_per_map() {
+        let column = parse_heights("10\n20\n", HeightFormat::Separated, false).unwrap();
+
+        assert_eq!((column.rows(), column.cols()), (2, 1));
+        assert_eq!(column[(1, 0)], Some(20));
+        assert_eq!(
+            parse_heights("10 2 30\n456\n", HeightFormat::Separated, false),
+            Err(ParseError {
+                line: 2,
+                reason: "row length differs from the first row"
+            })
+        );
+        assert_eq!(
+            parse_heights("456\n10 2 30\n", HeightFormat::Digits, true),
+            Err(ParseError {
+                line: 2,
+                reason: "invalid height"
+            })
+        );
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let input = "30373\n255\n65332\n";
+
+        assert_eq!(
+            parse_heights(input, HeightFormat::Digits, false),
+            Err(ParseError {
+                line: 2,
+                reason: "row length differs from the first row"
+            })
+        );
+        assert_eq!(get_grid(input), None);
+        assert!(input.parse::<Forest>().is_err());
+        assert_eq!(part1(input), None);
+
+        let heights = parse_heights(input, HeightFormat::Digits, true).unwrap();
+        let forest = Forest::from_heights(&heights);
+
+        assert_eq!(forest.tree(1, 3), None);
+        assert_eq!(forest.tree(1, 4), None);
+
+        // the missing cells below are the forest's edge
+        let tree = forest.tree(0, 3).unwrap();
+        assert!(tree.visible_from.contains(Edges::BOTTOM));
+        assert_eq!(tree.distances.bottom, 0);
+
+        let tree = forest.tree(2, 4).unwrap();
+        assert!(tree.visible_from.contains(Edges::TOP));
+        assert_eq!(tree.distances.top, 0);
+
+        // and so is the end of a short row
+        let tree = forest.tree(1, 2).unwrap();
+        assert!(tree.visible_from.contains(Edges::RIGHT));
+        assert_eq!(tree.distances.right, 0);
+        assert_eq!(tree.distances.left, 1);
+    }
 }
@@ -1,48 +1,489 @@
 use std::collections::HashSet;
+use std::ops::Add;
+use std::str::FromStr;
 
+/// A point on an `N`-dimensional grid. The puzzle's plane is `Coord<2>`, with
+/// `x` first and `y` growing upwards.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct Coord(isize, isize);
+pub struct Coord<const N: usize = 2>(pub [isize; N]);
 
-fn next(head: &Coord, tail: &Coord) -> Coord {
+impl<const N: usize> Coord<N> {
+    pub const ORIGIN: Self = Self([0; N]);
+}
+
+impl<const N: usize> Add for Coord<N> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a += b;
+        }
+
+        self
+    }
+}
+
+fn next<const N: usize>(head: &Coord<N>, tail: &Coord<N>) -> Coord<N> {
     let mut r = *tail;
-    let dx = head.0 - tail.0;
-    let dy = head.1 - tail.1;
+    let d: [isize; N] = std::array::from_fn(|i| head.0[i] - tail.0[i]);
 
-    if dx.abs() > 1 || dy.abs() > 1 {
-        r.0 += dx.clamp(-1, 1);
-        r.1 += dy.clamp(-1, 1);
+    if d.iter().any(|d| d.abs() > 1) {
+        for (r, d) in r.0.iter_mut().zip(d) {
+            *r += d.clamp(-1, 1);
+        }
     }
 
     r
 }
 
-fn nr_visited(input: &str, rope_len: usize) -> Option<usize> {
-    let mut visited = HashSet::new();
-    let mut rope = vec![Coord(0, 0); rope_len];
-
-    for cmd in input.lines() {
-        let (dir, v) = cmd.split_once(' ')?;
-        let val = v.parse::<isize>().ok()?;
-
-        for _ in 0..val {
-            let head = rope[0];
-            rope[0] = match dir {
-                "U" => Coord(head.0, head.1 + 1),
-                "D" => Coord(head.0, head.1 - 1),
-                "L" => Coord(head.0 - 1, head.1),
-                "R" => Coord(head.0 + 1, head.1),
-                _ => return None,
-            };
-
-            for i in 1..rope_len {
-                rope[i] = next(&rope[i - 1], &rope[i]);
+/// The letters naming each axis' positive and negative direction, in axis
+/// order: `R`/`L` for `x`, `U`/`D` for `y` and `F`/`B` for `z`.
+const AXES: [(char, char); 3] = [('R', 'L'), ('U', 'D'), ('F', 'B')];
+
+/// Parses a direction such as `U`, `DL` or `UFR` into the unit step it
+/// names. Each letter moves along one axis, so diagonals combine several.
+fn parse_direction<const N: usize>(s: &str) -> Result<Coord<N>, &'static str> {
+    let mut delta = Coord::ORIGIN;
+
+    if s.is_empty() {
+        return Err("unrecognized direction");
+    }
+
+    for c in s.chars() {
+        let (axis, step) = AXES
+            .iter()
+            .take(N)
+            .enumerate()
+            .find_map(|(i, &(pos, neg))| match c {
+                _ if c == pos => Some((i, 1)),
+                _ if c == neg => Some((i, -1)),
+                _ => None,
+            })
+            .ok_or("unrecognized direction")?;
+
+        if delta.0[axis] != 0 {
+            return Err("direction repeats an axis");
+        }
+        delta.0[axis] = step;
+    }
+
+    Ok(delta)
+}
+
+/// A head motion: `steps` unit steps of `delta`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Motion<const N: usize = 2> {
+    pub delta: Coord<N>,
+    pub steps: usize,
+}
+
+impl<const N: usize> FromStr for Motion<N> {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (dir, steps) = s.split_once(' ').ok_or("expected direction and steps")?;
+
+        Ok(Self {
+            delta: parse_direction(dir)?,
+            steps: steps.parse().or(Err("couldn't parse steps"))?,
+        })
+    }
+}
+
+pub fn parse_motions<const N: usize>(input: &str) -> Option<Vec<Motion<N>>> {
+    input.lines().map(|l| l.parse().ok()).collect()
+}
+
+/// A rope of knots, each following the one before it, that remembers every
+/// position each knot has been in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rope<const N: usize = 2> {
+    knots: Vec<Coord<N>>,
+    visited: Vec<HashSet<Coord<N>>>,
+}
+
+impl<const N: usize> Rope<N> {
+    /// A rope of `len` knots, all starting at the origin.
+    pub fn new(len: usize) -> Self {
+        Self {
+            knots: vec![Coord::ORIGIN; len],
+            visited: vec![HashSet::from([Coord::ORIGIN]); len],
+        }
+    }
+
+    pub fn knots(&self) -> &[Coord<N>] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> Option<Coord<N>> {
+        self.knots.first().copied()
+    }
+
+    pub fn tail(&self) -> Option<Coord<N>> {
+        self.knots.last().copied()
+    }
+
+    /// Every position knot `knot` (0 being the head) has been in.
+    pub fn visited(&self, knot: usize) -> Option<&HashSet<Coord<N>>> {
+        self.visited.get(knot)
+    }
+
+    /// Moves the head by `delta` and lets the other knots catch up.
+    pub fn step(&mut self, delta: Coord<N>) {
+        let Some(head) = self.knots.first_mut() else {
+            return;
+        };
+
+        *head = *head + delta;
+        self.visited[0].insert(*head);
+
+        for i in 1..self.knots.len() {
+            self.knots[i] = next(&self.knots[i - 1], &self.knots[i]);
+            self.visited[i].insert(self.knots[i]);
+        }
+    }
+
+    /// Starts a step-by-step simulation of the rope following `motions`.
+    pub fn simulate(self, motions: Vec<Motion<N>>) -> States<N> {
+        States {
+            rope: self,
+            motions: motions.into_iter(),
+            current: Motion {
+                delta: Coord::ORIGIN,
+                steps: 0,
+            },
+        }
+    }
+}
+
+/// A simulation in progress, expanding its motions into unit steps as it
+/// goes. See [`Rope::simulate`].
+#[derive(Clone, Debug)]
+pub struct States<const N: usize = 2> {
+    rope: Rope<N>,
+    motions: std::vec::IntoIter<Motion<N>>,
+    current: Motion<N>,
+}
+
+impl<const N: usize> States<N> {
+    /// The rope as of the last step taken.
+    pub fn rope(&self) -> &Rope<N> {
+        &self.rope
+    }
+
+    /// Takes the next unit step and returns the knot positions after it, or
+    /// `None` once the motions run out.
+    pub fn advance(&mut self) -> Option<&[Coord<N>]> {
+        while self.current.steps == 0 {
+            self.current = self.motions.next()?;
+        }
+
+        self.current.steps -= 1;
+        self.rope.step(self.current.delta);
+        Some(&self.rope.knots)
+    }
+
+    /// Takes up to `n` unit steps, returning how many were taken.
+    pub fn advance_by(&mut self, n: usize) -> usize {
+        (0..n).take_while(|_| self.advance().is_some()).count()
+    }
+}
+
+/// Yields a copy of the knot positions after each step. Use
+/// [`States::advance`] to step without copying.
+impl<const N: usize> Iterator for States<N> {
+    type Item = Vec<Coord<N>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance().map(<[_]>::to_vec)
+    }
+}
+
+/// An axis-aligned box of positions, inclusive on all sides.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Bounds<const N: usize = 2> {
+    pub min: Coord<N>,
+    pub max: Coord<N>,
+}
+
+impl<const N: usize> Bounds<N> {
+    /// The smallest box holding the start and every position the head goes
+    /// through. Knots never leave it, since they only ever move towards the
+    /// knot ahead of them.
+    pub fn covering(motions: &[Motion<N>]) -> Self {
+        let mut head = Coord::ORIGIN;
+        let mut bounds = Self {
+            min: head,
+            max: head,
+        };
+
+        // a motion moves in a straight line, so its end point is as far out
+        // as it gets
+        for m in motions {
+            for i in 0..N {
+                head.0[i] += m.delta.0[i] * m.steps as isize;
+                bounds.min.0[i] = bounds.min.0[i].min(head.0[i]);
+                bounds.max.0[i] = bounds.max.0[i].max(head.0[i]);
+            }
+        }
+
+        bounds
+    }
+
+    /// The number of positions along each axis.
+    fn extents(&self) -> [usize; N] {
+        std::array::from_fn(|i| (self.max.0[i] - self.min.0[i] + 1).max(0) as usize)
+    }
+
+    /// Where `c` goes in a row-major array covering the box, if it is inside.
+    fn index(&self, c: &Coord<N>) -> Option<usize> {
+        let extents = self.extents();
+        let mut index = 0;
+
+        for (i, &extent) in extents.iter().enumerate() {
+            let offset = usize::try_from(c.0[i] - self.min.0[i]).ok()?;
+            if offset >= extent {
+                return None;
             }
+            index = index * extent + offset;
+        }
 
-            visited.insert(rope[rope_len - 1]);
+        Some(index)
+    }
+}
+
+impl Rope {
+    /// The character the puzzle draws knot `i` with: `H` for the head, then
+    /// `1`, `2`, ... except that a two-knot rope's tail is `T`.
+    fn label(&self, i: usize) -> char {
+        match i {
+            0 => 'H',
+            1 if self.knots.len() == 2 => 'T',
+            _ => char::from_digit(i as u32, 36).unwrap_or('#'),
         }
     }
 
-    Some(visited.len())
+    /// Draws the rope the way the puzzle does, with `y` growing upwards.
+    /// Knots closer to the head cover the ones behind them, and the starting
+    /// point shows as `s` when uncovered.
+    pub fn render(&self, bounds: &Bounds) -> String {
+        let [min_x, _] = bounds.min.0;
+        let [_, max_y] = bounds.max.0;
+        let [width, height] = bounds.extents();
+        let mut rows = vec![vec!['.'; width]; height];
+        let mut draw = |Coord([x, y]): Coord, label| {
+            let x = x - min_x;
+            let y = max_y - y;
+
+            if (0..width as isize).contains(&x) && (0..height as isize).contains(&y) {
+                rows[y as usize][x as usize] = label;
+            }
+        };
+
+        draw(Coord::ORIGIN, 's');
+        for (i, &knot) in self.knots.iter().enumerate().rev() {
+            draw(knot, self.label(i));
+        }
+
+        rows.iter()
+            .map(|r| r.iter().collect::<String>() + "\n")
+            .collect()
+    }
+}
+
+/// Renders the rope before and after every unit step of `motions`, all
+/// frames covering the same area. Frames are drawn as they are asked for.
+pub fn animate(rope_len: usize, motions: Vec<Motion>) -> impl Iterator<Item = String> {
+    let bounds = Bounds::covering(&motions);
+    let rope = Rope::new(rope_len);
+    let first = rope.render(&bounds);
+    let mut states = rope.simulate(motions);
+
+    std::iter::once(first).chain(std::iter::from_fn(move || {
+        states.advance()?;
+        Some(states.rope().render(&bounds))
+    }))
+}
+
+/// Boxes with more positions than this are tracked in a hash set rather
+/// than a bitmap.
+const MAX_BITMAP_LEN: usize = 1 << 28;
+
+/// A set of positions, kept as a bitmap over a bounding box when that box is
+/// small enough and as a hash set otherwise.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VisitedSet<const N: usize = 2> {
+    Bitmap {
+        bounds: Bounds<N>,
+        words: Vec<u64>,
+        len: usize,
+    },
+    Sparse(HashSet<Coord<N>>),
+}
+
+impl<const N: usize> VisitedSet<N> {
+    /// An empty set for positions within `bounds`.
+    pub fn new(bounds: Bounds<N>) -> Self {
+        let nr_bits = bounds
+            .extents()
+            .iter()
+            .try_fold(1usize, |acc, &e| acc.checked_mul(e))
+            .filter(|&n| n <= MAX_BITMAP_LEN);
+
+        match nr_bits {
+            Some(n) => Self::Bitmap {
+                bounds,
+                words: vec![0; n.div_ceil(64)],
+                len: 0,
+            },
+            None => Self::Sparse(HashSet::new()),
+        }
+    }
+
+    /// Adds `c`, returning whether it wasn't there already. Positions
+    /// outside the bitmap's box turn it into a hash set.
+    pub fn insert(&mut self, c: Coord<N>) -> bool {
+        match self {
+            Self::Bitmap { bounds, words, len } => {
+                if let Some(i) = bounds.index(&c) {
+                    let (word, bit) = (i / 64, 1 << (i % 64));
+                    let new = words[word] & bit == 0;
+
+                    words[word] |= bit;
+                    *len += new as usize;
+                    return new;
+                }
+            }
+            Self::Sparse(set) => return set.insert(c),
+        }
+
+        let mut set = self.iter().collect::<HashSet<_>>();
+        let new = set.insert(c);
+
+        *self = Self::Sparse(set);
+        new
+    }
+
+    pub fn contains(&self, c: &Coord<N>) -> bool {
+        match self {
+            Self::Bitmap { bounds, words, .. } => bounds
+                .index(c)
+                .is_some_and(|i| words[i / 64] & (1 << (i % 64)) != 0),
+            Self::Sparse(set) => set.contains(c),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Bitmap { len, .. } => *len,
+            Self::Sparse(set) => set.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The positions in the set, in no particular order.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Coord<N>> + '_> {
+        match self {
+            Self::Bitmap { bounds, words, .. } => {
+                let extents = bounds.extents();
+
+                Box::new(
+                    words
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &word)| word != 0)
+                        .flat_map(|(w, &word)| {
+                            let rest = |&r: &u64| Some(r & (r - 1)).filter(|&r| r != 0);
+
+                            std::iter::successors(Some(word), rest)
+                                .map(move |rest| w * 64 + rest.trailing_zeros() as usize)
+                        })
+                        .map(move |mut i| {
+                            let mut c = bounds.min;
+
+                            for axis in (0..N).rev() {
+                                c.0[axis] += (i % extents[axis]) as isize;
+                                i /= extents[axis];
+                            }
+                            c
+                        }),
+                )
+            }
+            Self::Sparse(set) => Box::new(set.iter().copied()),
+        }
+    }
+}
+
+/// Whether the next step of `delta` moves every knot by `delta` as well. A
+/// knot then keeps the same offset from the one before it, so the same holds
+/// for every step after that.
+fn moves_rigidly<const N: usize>(knots: &[Coord<N>], delta: Coord<N>) -> bool {
+    knots
+        .windows(2)
+        .all(|w| next(&(w[0] + delta), &w[1]) == w[1] + delta)
+}
+
+/// The positions the tail of a `len`-knot rope visits over `motions`.
+///
+/// Only the tail is tracked. A step stops propagating at the first knot
+/// that doesn't move, and once the whole rope moves along with the head the
+/// rest of the motion is done in one go.
+pub fn tail_visits<const N: usize>(len: usize, motions: &[Motion<N>]) -> Option<VisitedSet<N>> {
+    let mut knots = vec![Coord::ORIGIN; len];
+    let mut visited = VisitedSet::new(Bounds::covering(motions));
+
+    visited.insert(*knots.last()?);
+
+    for m in motions {
+        let mut left = m.steps;
+        let mut check = true;
+
+        while left > 0 {
+            if check && moves_rigidly(&knots, m.delta) {
+                let mut tail = *knots.last()?;
+
+                for _ in 0..left {
+                    tail = tail + m.delta;
+                    visited.insert(tail);
+                }
+                for k in knots.iter_mut() {
+                    *k = Coord(std::array::from_fn(|i| {
+                        k.0[i] + m.delta.0[i] * left as isize
+                    }));
+                }
+                break;
+            }
+
+            let tail = *knots.last()?;
+
+            knots[0] = knots[0] + m.delta;
+            for i in 1..knots.len() {
+                let knot = next(&knots[i - 1], &knots[i]);
+
+                if knot == knots[i] {
+                    break;
+                }
+                knots[i] = knot;
+            }
+
+            // A rope that moves rigidly moves its tail by `delta` too, so
+            // it is only worth checking after a step that did.
+            check = *knots.last()? == tail + m.delta;
+            visited.insert(*knots.last()?);
+            left -= 1;
+        }
+    }
+
+    Some(visited)
+}
+
+fn nr_visited(input: &str, rope_len: usize) -> Option<usize> {
+    let motions = parse_motions::<2>(input)?;
+
+    Some(tail_visits(rope_len, &motions)?.len())
 }
 
 pub fn part1(input: &str) -> Option<usize> {
@@ -85,4 +526,219 @@ mod tests {
     fn part2_test() {
         assert_eq!(part2(TEST), Some(2482))
     }
+
+    #[test]
+    fn rope_states() {
+        let mut states = Rope::new(2).simulate(parse_motions(EXAMPLE1).unwrap());
+
+        assert_eq!(states.next(), Some(vec![Coord([1, 0]), Coord([0, 0])]));
+        assert_eq!(states.advance(), Some(&[Coord([2, 0]), Coord([1, 0])][..]));
+        assert_eq!(states.by_ref().count(), 22);
+        assert_eq!(states.advance(), None);
+
+        let rope = states.rope();
+        assert_eq!(rope.head(), Some(Coord([2, 2])));
+        assert_eq!(rope.tail(), Some(Coord([1, 2])));
+        assert_eq!(rope.visited(1).unwrap().len(), 13);
+        assert!(rope.visited(0).unwrap().contains(&Coord([4, 4])));
+        assert_eq!(rope.visited(2), None);
+
+        let endless = Motion {
+            delta: Coord([1, 0]),
+            steps: usize::MAX,
+        };
+        let mut states = Rope::new(2).simulate(vec![endless]);
+        assert_eq!(states.advance_by(3), 3);
+        assert_eq!(states.rope().head(), Some(Coord([3, 0])));
+    }
+
+    #[test]
+    fn rope_visited_per_knot() {
+        let mut states = Rope::new(10).simulate(parse_motions(EXAMPLE2).unwrap());
+        let last = states.by_ref().last().unwrap();
+
+        assert_eq!(last.len(), 10);
+        assert_eq!(last[0], Coord([-11, 15]));
+        assert_eq!(states.rope().visited(9).unwrap().len(), 36);
+        assert!(states.rope().visited(1).unwrap().len() > 36);
+    }
+
+    #[test]
+    fn render_example1() {
+        let frames = animate(2, parse_motions(EXAMPLE1).unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(frames.len(), 25);
+        assert_eq!(frames[0], "......\n......\n......\n......\nH.....\n");
+        assert_eq!(frames[1], "......\n......\n......\n......\nTH....\n");
+        assert_eq!(frames[8], "....H.\n....T.\n......\n......\ns.....\n");
+        assert_eq!(frames[24], "......\n......\n.TH...\n......\ns.....\n");
+    }
+
+    #[test]
+    fn bounds_of_long_motions() {
+        let motions = parse_motions::<2>("R 1000000\nU 3\nL 2000000\nD 5\n").unwrap();
+
+        assert_eq!(
+            Bounds::covering(&motions),
+            Bounds {
+                min: Coord([-1000000, -2]),
+                max: Coord([1000000, 3]),
+            }
+        );
+    }
+
+    #[test]
+    fn render_example2() {
+        let motions = parse_motions(EXAMPLE2).unwrap();
+        let bounds = Bounds::covering(&motions);
+        let mut states = Rope::new(10).simulate(motions);
+
+        states.by_ref().take(5).count();
+        assert_eq!(
+            states.rope().render(&bounds).lines().nth(15),
+            Some("...........54321H.........")
+        );
+
+        states.by_ref().count();
+        let expected = "H.........................
+1.........................
+2.........................
+3.........................
+4.........................
+5.........................
+6.........................
+7.........................
+8.........................
+9.........................
+..........................
+..........................
+..........................
+..........................
+..........................
+...........s..............
+..........................
+..........................
+..........................
+..........................
+..........................
+";
+        assert_eq!(states.rope().render(&bounds), expected);
+    }
+
+    #[test]
+    fn directions() {
+        assert_eq!(parse_direction::<2>("U"), Ok(Coord([0, 1])));
+        assert_eq!(parse_direction::<2>("DL"), Ok(Coord([-1, -1])));
+        assert_eq!(parse_direction::<2>("RU"), Ok(Coord([1, 1])));
+        assert_eq!(parse_direction::<3>("UFR"), Ok(Coord([1, 1, 1])));
+        assert_eq!(parse_direction::<2>("UD"), Err("direction repeats an axis"));
+        assert_eq!(parse_direction::<2>("F"), Err("unrecognized direction"));
+        assert_eq!(parse_direction::<2>(""), Err("unrecognized direction"));
+    }
+
+    #[test]
+    fn diagonal_moves() {
+        let motions = parse_motions("UR 3\nDR 2\nL 1\n").unwrap();
+        let mut states = Rope::new(3).simulate(motions);
+
+        assert_eq!(
+            states.next(),
+            Some(vec![Coord([1, 1]), Coord::ORIGIN, Coord::ORIGIN])
+        );
+        assert_eq!(
+            states.nth(1),
+            Some(vec![Coord([3, 3]), Coord([2, 2]), Coord([1, 1])])
+        );
+        assert_eq!(
+            states.last(),
+            Some(vec![Coord([4, 1]), Coord([4, 1]), Coord([3, 1])])
+        );
+    }
+
+    #[test]
+    fn rope_in_3d() {
+        let mut flat = Rope::new(10).simulate(parse_motions::<3>(EXAMPLE2).unwrap());
+        flat.by_ref().count();
+        assert_eq!(flat.rope().visited(9).unwrap().len(), 36);
+
+        let mut states = Rope::new(2).simulate(parse_motions("F 3\nUB 1\n").unwrap());
+        assert_eq!(
+            states.nth(3),
+            Some(vec![Coord([0, 1, 2]), Coord([0, 0, 2])])
+        );
+
+        let states = Rope::new(2).simulate(parse_motions("UFR 2\n").unwrap());
+        assert_eq!(
+            states.last(),
+            Some(vec![Coord([2, 2, 2]), Coord([1, 1, 1])])
+        );
+    }
+
+    #[test]
+    fn visited_set() {
+        let bounds = Bounds {
+            min: Coord([-1, -2]),
+            max: Coord([1, 2]),
+        };
+        let mut set = VisitedSet::new(bounds);
+
+        assert!(matches!(set, VisitedSet::Bitmap { .. }));
+        assert!(set.insert(Coord([-1, -2])));
+        assert!(set.insert(Coord([1, 2])));
+        assert!(!set.insert(Coord([1, 2])));
+        assert!(set.contains(&Coord([-1, -2])));
+        assert!(!set.contains(&Coord([0, 0])));
+        assert_eq!(set.len(), 2);
+
+        assert!(set.insert(Coord([5, 5])));
+        assert!(matches!(set, VisitedSet::Sparse(_)));
+        assert_eq!(
+            set.iter().collect::<HashSet<_>>(),
+            HashSet::from([Coord([-1, -2]), Coord([1, 2]), Coord([5, 5])])
+        );
+
+        let huge = Bounds {
+            min: Coord([0, 0, 0]),
+            max: Coord([1 << 20, 1 << 20, 1 << 20]),
+        };
+        assert!(matches!(VisitedSet::new(huge), VisitedSet::Sparse(_)));
+    }
+
+    fn stepped_tail_visits<const N: usize>(len: usize, motions: &[Motion<N>]) -> HashSet<Coord<N>> {
+        let mut states = Rope::new(len).simulate(motions.to_vec());
+
+        states.advance_by(usize::MAX);
+        states.rope().visited(len - 1).unwrap().clone()
+    }
+
+    #[test]
+    fn tail_visits_match_rope() {
+        let flat = parse_motions::<2>("R 30\nUL 120\nD 40\nL 3\nDR 25\nU 1\n").unwrap();
+        let deep = parse_motions::<3>("F 300\nUR 300\nB 5\nDLF 40\nU 2\n").unwrap();
+
+        for len in [1, 2, 5, 10, 40] {
+            let fast = tail_visits(len, &flat).unwrap();
+            assert_eq!(
+                fast.iter().collect::<HashSet<_>>(),
+                stepped_tail_visits(len, &flat)
+            );
+
+            let fast = tail_visits(len, &deep).unwrap();
+            assert_eq!(
+                fast.iter().collect::<HashSet<_>>(),
+                stepped_tail_visits(len, &deep)
+            );
+        }
+
+        assert!(tail_visits(0, &flat).is_none());
+    }
+
+    #[test]
+    fn tail_visits_long_moves() {
+        let motions = parse_motions::<2>("R 1000000\nL 1000000\n").unwrap();
+        let visited = tail_visits(1000, &motions).unwrap();
+
+        assert!(matches!(visited, VisitedSet::Bitmap { .. }));
+        assert_eq!(visited.len(), 999_002);
+    }
 }
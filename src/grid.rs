@@ -0,0 +1,228 @@
+use std::ops::{Index, IndexMut};
+
+/// A rectangular grid stored row by row in a single allocation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    rows: usize,
+    cols: usize,
+}
+
+/// The four directions along the grid axes.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Orthogonal directions plus diagonals.
+pub const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+impl<T> Grid<T> {
+    /// Wraps `cells`, laid out row by row. Fails if there aren't exactly
+    /// `rows * cols` of them.
+    pub fn new(rows: usize, cols: usize, cells: Vec<T>) -> Option<Self> {
+        if rows.checked_mul(cols)? != cells.len() {
+            return None;
+        }
+
+        Some(Self { cells, rows, cols })
+    }
+
+    /// Builds a grid from its rows, which must all be the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let nr_rows = rows.len();
+        let nr_cols = rows.first().map(Vec::len).unwrap_or_default();
+
+        if rows.iter().any(|r| r.len() != nr_cols) {
+            return None;
+        }
+
+        Self::new(nr_rows, nr_cols, rows.into_iter().flatten().collect())
+    }
+
+    /// Parses a character map, one row per line, converting each character
+    /// with `f`. Fails on ragged rows or if `f` rejects a character.
+    pub fn parse<F>(input: &str, mut f: F) -> Option<Self>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let rows = input
+            .lines()
+            .map(|l| l.chars().map(&mut f).collect::<Option<Vec<_>>>())
+            .collect::<Option<Vec<_>>>()?;
+
+        Self::from_rows(rows)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn contains(&self, r: usize, c: usize) -> bool {
+        r < self.rows && c < self.cols
+    }
+
+    pub fn get(&self, r: usize, c: usize) -> Option<&T> {
+        if self.contains(r, c) {
+            self.cells.get(r * self.cols + c)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, r: usize, c: usize) -> Option<&mut T> {
+        if self.contains(r, c) {
+            self.cells.get_mut(r * self.cols + c)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, r: usize) -> Option<&[T]> {
+        if r < self.rows {
+            Some(&self.cells[r * self.cols..(r + 1) * self.cols])
+        } else {
+            None
+        }
+    }
+
+    /// The cells of column `c`, top to bottom. A grid without rows still has
+    /// its columns, they are just empty.
+    pub fn column(
+        &self,
+        c: usize,
+    ) -> Option<impl DoubleEndedIterator<Item = &T> + ExactSizeIterator + Clone> {
+        if c < self.cols {
+            Some(self.cells.get(c..).unwrap_or(&[]).iter().step_by(self.cols))
+        } else {
+            None
+        }
+    }
+
+    /// Every cell along with its `(row, column)` position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let cols = self.cols;
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, v)| ((i / cols, i % cols), v))
+    }
+
+    /// Moves from `(r, c)` by `(dr, dc)`, if that stays inside the grid.
+    pub fn offset(&self, r: usize, c: usize, (dr, dc): (isize, isize)) -> Option<(usize, usize)> {
+        let r = r.checked_add_signed(dr)?;
+        let c = c.checked_add_signed(dc)?;
+
+        if self.contains(r, c) {
+            Some((r, c))
+        } else {
+            None
+        }
+    }
+
+    /// Positions next to `(r, c)` in the given directions that are inside the
+    /// grid, such as [`ORTHOGONAL`] or [`ALL_DIRECTIONS`].
+    pub fn neighbors<'a>(
+        &'a self,
+        r: usize,
+        c: usize,
+        directions: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        directions.iter().filter_map(move |&d| self.offset(r, c, d))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+}
+
+impl Grid<[u8; 3]> {
+    /// Encodes the grid as a binary PPM (P6) image, one pixel per cell.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut res = format!("P6\n{} {}\n255\n", self.cols, self.rows).into_bytes();
+
+        res.extend(self.cells.iter().flatten());
+        res
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (r, c): (usize, usize)) -> &Self::Output {
+        self.get(r, c).expect("grid position out of bounds")
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Self::Output {
+        self.get_mut(r, c).expect("grid position out of bounds")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let grid = Grid::parse("123\n456\n", |c| c.to_digit(10)).unwrap();
+
+        assert_eq!((grid.rows(), grid.cols()), (2, 3));
+        assert_eq!(grid.row(1), Some(&[4, 5, 6][..]));
+        assert_eq!(grid.column(2).unwrap().copied().collect::<Vec<_>>(), [3, 6]);
+        assert_eq!(grid[(1, 0)], 4);
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+        assert!(grid.column(3).is_none());
+
+        assert_eq!(Grid::parse("12\n3\n", |c| c.to_digit(10)), None);
+        assert_eq!(Grid::parse("1x\n", |c| c.to_digit(10)), None);
+    }
+
+    #[test]
+    fn empty_columns() {
+        let grid = Grid::<u8>::new(0, 3, vec![]).unwrap();
+
+        assert_eq!(grid.column(1).map(|c| c.count()), Some(0));
+        assert!(grid.column(3).is_none());
+    }
+
+    #[test]
+    fn neighbors() {
+        let grid = Grid::new(2, 3, vec![0; 6]).unwrap();
+
+        assert_eq!(
+            grid.neighbors(0, 0, &ORTHOGONAL).collect::<Vec<_>>(),
+            [(1, 0), (0, 1)]
+        );
+        assert_eq!(grid.neighbors(1, 1, &ALL_DIRECTIONS).count(), 5);
+        assert_eq!(grid.offset(1, 2, (0, 1)), None);
+        assert_eq!(Grid::new(2, 2, vec![0; 3]), None);
+    }
+
+    #[test]
+    fn ppm() {
+        let grid = Grid::new(1, 2, vec![[255, 0, 0], [0, 0, 255]]).unwrap();
+
+        assert_eq!(grid.to_ppm(), b"P6\n2 1\n255\n\xff\0\0\0\0\xff");
+    }
+}
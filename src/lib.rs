@@ -9,3 +9,6 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod error;
+pub mod grid;
+pub mod ocr;
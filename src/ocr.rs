@@ -0,0 +1,142 @@
+use crate::grid::Grid;
+use std::fmt;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+
+/// Glyphs start every this many columns, leaving a blank one in between.
+const GLYPH_PITCH: usize = GLYPH_WIDTH + 1;
+
+/// The letters of the puzzles' 4×6 font, row by row.
+const FONT: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OcrError {
+    /// The text isn't a rectangle of `#` and `.`.
+    Malformed,
+    /// The image isn't one glyph tall.
+    BadHeight(usize),
+    /// The glyph at `index`, counting from the left, isn't a known letter.
+    UnknownGlyph { index: usize, glyph: String },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "image isn't a rectangle of '#' and '.'"),
+            Self::BadHeight(rows) => {
+                write!(f, "image is {} rows tall, expected {}", rows, GLYPH_HEIGHT)
+            }
+            Self::UnknownGlyph { index, glyph } => {
+                write!(f, "unrecognized glyph #{}:", index)?;
+                for row in glyph.as_bytes().chunks(GLYPH_WIDTH) {
+                    write!(f, "\n{}", String::from_utf8_lossy(row))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Reads the letters spelled out by lit pixels in `image`.
+pub fn read(image: &Grid<bool>) -> Result<String, OcrError> {
+    if image.rows() != GLYPH_HEIGHT {
+        return Err(OcrError::BadHeight(image.rows()));
+    }
+
+    (0..image.cols().div_ceil(GLYPH_PITCH))
+        .map(|index| {
+            let glyph = (0..GLYPH_HEIGHT)
+                .flat_map(|r| (0..GLYPH_WIDTH).map(move |c| (r, index * GLYPH_PITCH + c)))
+                .map(|(r, c)| match image.get(r, c) {
+                    Some(true) => '#',
+                    _ => '.',
+                })
+                .collect::<String>();
+
+            FONT.iter()
+                .find(|(_, g)| *g == glyph)
+                .map(|&(letter, _)| letter)
+                .ok_or(OcrError::UnknownGlyph { index, glyph })
+        })
+        .collect()
+}
+
+/// Like [`read`], for an image drawn with `#` and `.`.
+pub fn read_str(image: &str) -> Result<String, OcrError> {
+    let grid = Grid::parse(image, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
+    .ok_or(OcrError::Malformed)?;
+
+    read(&grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PART2_EXAMPLE_EXP: &str = include_str!("../input/day10/expected_part2_example.txt");
+    const PART2_TEST_EXP: &str = include_str!("../input/day10/expected_part2_test.txt");
+
+    #[test]
+    fn read_test_output() {
+        assert_eq!(read_str(PART2_TEST_EXP), Ok("PLULKBZH".to_string()));
+    }
+
+    #[test]
+    fn font_round_trip() {
+        let mut rows = vec![String::new(); GLYPH_HEIGHT];
+
+        for (_, glyph) in FONT {
+            for (r, row) in glyph.as_bytes().chunks(GLYPH_WIDTH).enumerate() {
+                rows[r] += std::str::from_utf8(row).unwrap();
+                rows[r] += ".";
+            }
+        }
+
+        let letters = FONT.iter().map(|&(l, _)| l).collect::<String>();
+        assert_eq!(letters, "ABCEFGHIJKLOPRSUYZ");
+        assert_eq!(read_str(&rows.join("\n")), Ok(letters));
+    }
+
+    #[test]
+    fn unrecognized_glyphs() {
+        assert_eq!(
+            read_str(PART2_EXAMPLE_EXP),
+            Err(OcrError::UnknownGlyph {
+                index: 0,
+                glyph: "##..###.################".to_string()
+            })
+        );
+        assert_eq!(
+            read_str(PART2_EXAMPLE_EXP).unwrap_err().to_string(),
+            "unrecognized glyph #0:\n##..\n###.\n####\n####\n####\n####"
+        );
+        assert_eq!(read_str("#..#\n"), Err(OcrError::BadHeight(1)));
+        assert_eq!(read_str("#x\n"), Err(OcrError::Malformed));
+    }
+}